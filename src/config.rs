@@ -0,0 +1,77 @@
+//! # Config
+//! Parsing for `edit_config`, which lets the user edit a configuration file until it is valid.
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unstable_features,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+/* Third Party */
+use serde::de::DeserializeOwned;
+
+/* Every line Scrawl adds to explain a parse error starts with this */
+const ERROR_MARKER: &str = "scrawl error: ";
+
+/// The formats `edit_config` can parse, each behind the feature of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// JSON, parsed with `serde_json`.
+    #[cfg(feature = "json")]
+    Json,
+    /// TOML, parsed with `toml`.
+    #[cfg(feature = "toml")]
+    Toml,
+    /// YAML, parsed with `serde_yaml`.
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Parses `text` in this format, returning the parser's error message on failure.
+    pub(crate) fn parse<T: DeserializeOwned>(self, text: &str) -> Result<T, String> {
+        match self {
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => toml::from_str(text).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string()),
+        }
+    }
+
+    /* JSON has no comments, but the lines are removed again before parsing anyway */
+    fn comment(self) -> &'static str {
+        match self {
+            #[cfg(feature = "json")]
+            ConfigFormat::Json => "// ",
+            #[cfg(feature = "toml")]
+            ConfigFormat::Toml => "# ",
+            #[cfg(feature = "yaml")]
+            ConfigFormat::Yaml => "# ",
+        }
+    }
+
+    /// Returns `text` with an explanation of `error` commented in at the top.
+    pub(crate) fn annotate(self, text: &str, error: &str) -> String {
+        let mut annotated: String = error
+            .lines()
+            .map(|line| format!("{}{ERROR_MARKER}{line}\n", self.comment()))
+            .collect();
+        annotated.push_str(text);
+        annotated
+    }
+
+    /// Removes the lines added by `annotate`.
+    pub(crate) fn strip_annotations(self, text: &str) -> String {
+        let marker = format!("{}{ERROR_MARKER}", self.comment());
+        text.split_inclusive('\n')
+            .filter(|line| !line.starts_with(&marker))
+            .collect()
+    }
+}
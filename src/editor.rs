@@ -15,30 +15,91 @@
 /* Standard Library */
 use std::{
     env,
-    error::Error,
     ffi::{OsStr, OsString},
-    fs,
-    io::BufReader,
+    fmt, fs,
+    io::{self, BufReader, Cursor, IsTerminal, Read, Seek, SeekFrom, Write},
     ops::Drop,
     path::{Path, PathBuf},
-    process::Command,
-    sync::atomic::{AtomicUsize, Ordering},
-    time::{SystemTime, UNIX_EPOCH},
+    process::{Child, ChildStderr, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-/* Trait to keep things DRY */
-trait ScrawlState {}
+/* Internal Modules */
+use crate::error::ScrawlError;
+use crate::logging::{debug, error, info, warn};
 
 /* Constants */
 const SCRAWL_TEMP_DIR: &str = "xvrqt_scrawl";
+const PROJECT_DIR: &str = ".scrawl";
 const DEFAULT_EXT: &str = ".txt";
+const BINARY_EXT: &str = ".bin";
+const WRAP_START: &str = "------------------------ >8 ------------------------";
+const WRAP_END: &str = "------------------------ 8< ------------------------";
+const DEFAULT_ENV_VARS: [&str; 3] = ["SCRAWL_EDITOR", "VISUAL", "EDITOR"];
+const TEST_REPLACEMENT_VAR: &str = "SCRAWL_TEST_REPLACEMENT";
+const TRANSFORM_EDITOR: &str = "transform";
+const STDIN_EDITOR: &str = "stdin";
+const FILE_TOKENS: [&str; 5] = ["{}", "{path}", "{name}", "{ext}", "{dir}"];
+/* How long `open_with_os_default` waits for saves to stop when the opener returns at once */
+const OPENER_QUIET_PERIOD: Duration = Duration::from_secs(2);
+/* How long `wait_on_close` waits in all before giving up on the user */
+const WAIT_ON_CLOSE_LIMIT: Duration = Duration::from_secs(60 * 60);
+/* How long to wait for the rest of the editor's stderr once it has exited */
+const STDERR_GRACE_PERIOD: Duration = Duration::from_millis(100);
 static TEMP_FILE_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 /* The struct used to construct an Editor */
 #[derive(Debug)]
 /// This is the struct that allows the caller to customize which editor is called, what it is seeded with, and more.
 pub struct Editor<S: EditorState> {
-    extension: String,
+    extension: Option<String>,
+    temp_subdir: String,
+    project: Option<PathBuf>,
+    create_temp_parents: bool,
+    debug_filenames: bool,
+    env_vars: Vec<OsString>,
+    ignore_env: bool,
+    os_editors: Vec<(Os, OsString)>,
+    create: bool,
+    read_only: bool,
+    new_window: bool,
+    quiet: bool,
+    require_interactive: bool,
+    pipe_mode: bool,
+    binary: bool,
+    newline: NewlineStyle,
+    encoding: Option<String>,
+    wrap: Option<(String, String)>,
+    sentinels: (String, String),
+    flag_injection: FlagInjection,
+    max_size: Option<u64>,
+    post_process: Option<Program>,
+    command_hook: Option<CommandHook>,
+    on_read: Option<ReadHook>,
+    on_fallback: Option<FallbackHook>,
+    on_empty: OnEmpty,
+    abort_on: Option<String>,
+    reuse_buffer: bool,
+    memory_backed: bool,
+    reused: Option<Arc<SharedBuffer>>,
+    wait_on_close: Option<Duration>,
+    wait_on_close_limit: Duration,
+    cursor_marker: Option<String>,
+    cursor_at_end: bool,
+    selection: Option<(Position, Position)>,
+    cursor: Option<Position>,
+    companions: Vec<PathBuf>,
+    layout: Layout,
+    current_dir: Option<PathBuf>,
+    original_dir: Option<PathBuf>,
+    stdin: Option<Stdio>,
+    stdout: Option<Stdio>,
+    stderr: Option<Stdio>,
     state: S,
 }
 
@@ -46,241 +107,3537 @@ pub struct Editor<S: EditorState> {
 /// Creates a new Editor struct, ready for customizing or opening.
 pub fn new() -> Editor<DefaultState> {
     Editor {
-        extension: String::from(DEFAULT_EXT),
+        extension: None,
+        temp_subdir: String::from(SCRAWL_TEMP_DIR),
+        project: None,
+        create_temp_parents: true,
+        debug_filenames: false,
+        env_vars: DEFAULT_ENV_VARS.iter().map(OsString::from).collect(),
+        ignore_env: false,
+        os_editors: vec![],
+        create: false,
+        read_only: false,
+        new_window: false,
+        quiet: false,
+        require_interactive: false,
+        pipe_mode: false,
+        binary: false,
+        newline: NewlineStyle::Preserve,
+        encoding: None,
+        wrap: None,
+        sentinels: (WRAP_START.into(), WRAP_END.into()),
+        flag_injection: FlagInjection::Auto,
+        max_size: None,
+        post_process: None,
+        command_hook: None,
+        on_read: None,
+        on_fallback: None,
+        on_empty: OnEmpty::Keep,
+        abort_on: None,
+        reuse_buffer: false,
+        memory_backed: false,
+        reused: None,
+        wait_on_close: None,
+        wait_on_close_limit: WAIT_ON_CLOSE_LIMIT,
+        cursor_marker: None,
+        cursor_at_end: false,
+        selection: None,
+        cursor: None,
+        companions: Vec::new(),
+        layout: Layout::Split,
+        current_dir: None,
+        original_dir: env::current_dir().ok(),
+        stdin: None,
+        stdout: None,
+        stderr: None,
         state: DefaultState {},
     }
 }
 
-/* Trait that is used to keep track which state the Editor is in */
-/// Used to keep track of which state the Editor struct is in while it's constructed.
-pub trait EditorState {}
+/* Editors with their command lines already worked out */
+/// An Editor that opens Vim. Vim blocks until it's closed, so no extra flags are needed, and options like `read_only` and `cursor_at_end` pass it the right ones.
+pub fn vim() -> Editor<SpecificEditorState> {
+    new().editor("vim")
+}
 
-/* These function are available to all states of the Editor. Utility functions */
-impl<S: EditorState> Editor<S> {
-    /// Creates a temporary file to use a buffer for the user's editor.
-    fn create_buffer_file(&mut self, contents: Contents) -> Result<PathBuf, Box<dyn Error>> {
-        /* Check create a Scawl directory in the user's tmp/ directory */
-        let mut temp_dir = env::temp_dir();
-        temp_dir.push(SCRAWL_TEMP_DIR);
-        /* Create it if it doesn't already exist */
-        if fs::metadata(&temp_dir).is_err() {
-            fs::create_dir(&temp_dir)?
-        };
+/// An Editor that opens nano, which blocks until it's closed and gets the right flags for options like `read_only` and `cursor_at_end`.
+pub fn nano() -> Editor<SpecificEditorState> {
+    new().editor("nano")
+}
 
-        /* Generate unique path to a temporary file */
-        let i = TEMP_FILE_COUNT.fetch_add(1, Ordering::SeqCst);
-        let ts = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_or_else(|_| 0, |v| v.as_secs());
-        let ext = &self.extension;
-        let process_id = std::process::id();
-        /* e.g. 1674864208_123_17.txt */
-        let temp_file = format!("{ts}_{process_id}_{i}{ext}");
-
-        /* Create the file path & file */
-        temp_dir.push(&temp_file);
-        let temp_file_path = temp_dir;
-        fs::File::create(&temp_file_path)?;
-
-        /* Check if we need to seed the contents of this temporary file */
-        match contents {
-            Contents::FromFile(source) => fs::copy(source, &temp_file_path).map(|_| ())?,
-            Contents::FromString(s) => fs::write(&temp_file_path, s)?,
-            _ => (),
+/// An Editor that opens Emacs, which blocks until it's closed and gets the right flags for positioning the cursor.
+pub fn emacs() -> Editor<SpecificEditorState> {
+    new().editor("emacs")
+}
+
+/// An Editor that opens Visual Studio Code with `--wait`, so Scrawl waits for the buffer's tab to be closed instead of returning as soon as the window opens. The cursor is positioned with `--goto`.
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let input = scrawl::editor::vscode()
+///     .ext("md")
+///     .cursor_at_end(true)
+///     .open(scrawl::Contents::FromString(&"# Title\n"))?;
+/// println!("{}", input.to_string()?);
+/// #   Ok(())
+/// # }
+/// ```
+pub fn vscode() -> Editor<SpecificEditorState> {
+    new().editor_argv(["code", "--wait"])
+}
+
+/* `text`, ending in a newline unless it's empty */
+fn with_newline(text: &str) -> String {
+    match text {
+        "" => String::new(),
+        text if text.ends_with('\n') => text.into(),
+        text => format!("{text}\n"),
+    }
+}
+
+/* The part of `bytes` between the `start` and `end` sentinel lines */
+fn unwrap_region(bytes: Vec<u8>, (start, end): &(String, String)) -> Vec<u8> {
+    let mut from = 0;
+    let mut to = bytes.len();
+    let mut offset = 0;
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        let trimmed = line.strip_suffix(b"\n").unwrap_or(line);
+        let trimmed = trimmed.strip_suffix(b"\r").unwrap_or(trimmed);
+        if from == 0 && trimmed == start.as_bytes() {
+            from = offset + line.len();
+        } else if trimmed == end.as_bytes() && offset >= from {
+            to = offset;
         }
+        offset += line.len();
+    }
+    bytes[from..to.max(from)].to_vec()
+}
 
-        /* Return the path */
-        Ok(temp_file_path)
+/* Says which editor failed to start, and why */
+fn open_error(program: &OsStr, e: io::Error) -> ScrawlError {
+    error!("could not start {}: {e}", program.to_string_lossy());
+    ScrawlError::FailedToOpenEditor(format!("{}: {e}", program.to_string_lossy()))
+}
+
+/* Transcodes UTF-8 text into the encoding called `label`; anything else is assumed to be in it already */
+#[cfg(feature = "encoding_rs")]
+fn encode(bytes: Vec<u8>, label: &str) -> Result<Vec<u8>, ScrawlError> {
+    let encoding = encoding_rs::Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| ScrawlError::UnknownEncoding(label.into()))?;
+    match String::from_utf8(bytes) {
+        Ok(text) => Ok(encoding.encode(&text).0.into_owned()),
+        Err(e) => Ok(e.into_bytes()),
     }
+}
 
-    /// Returns the name of the editor to use if user specified, or a list of editors to try if Default is selected.
-    fn get_editor_programs(&self) -> Vec<OsString> {
-        let mut programs = Vec::with_capacity(3);
-        /* Check the usual ENV variables for programs */
-        if let Ok(p) = env::var("VISUAL") {
-            programs.push(OsString::from(p))
-        };
-        if let Ok(p) = env::var("EDITOR") {
-            programs.push(OsString::from(p))
-        };
+#[cfg(not(feature = "encoding_rs"))]
+fn encode(bytes: Vec<u8>, _label: &str) -> Result<Vec<u8>, ScrawlError> {
+    Ok(bytes)
+}
 
-        /* Add some common programs */
-        if cfg!(windows) {
-            programs.push("notepad.exe".into());
-        } else {
-            let p: Vec<OsString> = vec![
-                "vim".into(),
-                "neovim".into(),
-                "nvim".into(),
-                "nano".into(),
-                "emacs".into(),
-                "mcedit".into(),
-                "tilde".into(),
-                "micro".into(),
-                "helix".into(),
-                "ne".into(),
-                "vi".into(),
-            ];
-            programs.extend_from_slice(&p);
+/* Decodes text in the encoding called `label` into UTF-8, replacing anything malformed */
+#[cfg(feature = "encoding_rs")]
+fn decode(bytes: Vec<u8>, label: &str) -> Vec<u8> {
+    match encoding_rs::Encoding::for_label(label.as_bytes()) {
+        Some(encoding) => encoding
+            .decode_without_bom_handling(&bytes)
+            .0
+            .into_owned()
+            .into_bytes(),
+        None => bytes,
+    }
+}
+
+#[cfg(not(feature = "encoding_rs"))]
+fn decode(bytes: Vec<u8>, _label: &str) -> Vec<u8> {
+    bytes
+}
+
+/* The copy of the editor's stderr being kept by `tee_stderr` */
+#[derive(Debug)]
+struct StderrTee {
+    kept: Arc<Mutex<Vec<u8>>>,
+    done: mpsc::Receiver<()>,
+}
+
+impl StderrTee {
+    /* What has been kept so far, once the pipe closes or the grace period is up. Anything the editor forked off may hold the pipe open indefinitely, so this can't wait for the end */
+    fn finish(self) -> Vec<u8> {
+        let _ = self.done.recv_timeout(STDERR_GRACE_PERIOD);
+        let kept = self.kept.lock().map(|mut kept| std::mem::take(&mut *kept));
+        kept.unwrap_or_default()
+    }
+}
+
+/* Passes the editor's stderr through to ours as it's written, keeping a copy */
+fn tee_stderr(mut stderr: ChildStderr) -> StderrTee {
+    let kept = Arc::new(Mutex::new(Vec::new()));
+    let (finished, done) = mpsc::channel();
+    let copy = Arc::clone(&kept);
+    thread::spawn(move || {
+        let mut chunk = [0; 4096];
+        while let Ok(read @ 1..) = stderr.read(&mut chunk) {
+            let _ = io::stderr().write_all(&chunk[..read]);
+            if let Ok(mut copy) = copy.lock() {
+                copy.extend_from_slice(&chunk[..read]);
+            }
+        }
+        let _ = finished.send(());
+    });
+    StderrTee { kept, done }
+}
+
+/* Where buffers can go, best first, for systems where the usual temporary directory can't be written to */
+fn temp_dir_candidates() -> Vec<PathBuf> {
+    let home = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    let mut candidates = vec![env::temp_dir()];
+    candidates.extend(
+        ["TMPDIR", "XDG_RUNTIME_DIR"]
+            .into_iter()
+            .filter_map(env::var_os)
+            .map(PathBuf::from),
+    );
+    candidates.extend(env::current_dir());
+    candidates.extend(env::var_os(home).map(PathBuf::from));
+
+    let mut unique: Vec<PathBuf> = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        if !candidate.as_os_str().is_empty() && !unique.contains(&candidate) {
+            unique.push(candidate);
         }
-        programs
     }
+    unique
 }
 
-/* The default EditorState, in builder mode */
-#[derive(Debug, Clone, Copy)]
-/// Holds the data and implementation for the initial state of the Editor struct.
-pub struct DefaultState {}
-impl EditorState for DefaultState {}
+/* Creates the buffer file `name` in `dir`, creating `dir` (and its missing parents, if `parents`) too if needed */
+fn create_temp_file(dir: &Path, name: &str, parents: bool) -> io::Result<fs::File> {
+    /* Another Scrawl may be racing to create the directory */
+    let created = if parents {
+        fs::create_dir_all(dir)
+    } else {
+        fs::create_dir(dir)
+    };
+    match created {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => return Err(e),
+        _ => (),
+    }
+    fs::File::create(dir.join(name))
+}
 
-impl Editor<DefaultState> {
-    /// Specify which extension should be used on the temporary file (often used by text editors to infer syntax highlighting).
-    pub fn ext<S: AsRef<str>>(&mut self, ext: S) -> &mut Self {
-        self.extension = ext.as_ref().into();
-        self
+/* An anonymous memory file named `name`, and a path the editor can open it by */
+#[cfg(all(feature = "memfd", target_os = "linux"))]
+fn memory_file(name: &str) -> Option<(PathBuf, fs::File)> {
+    use rustix::fs::{memfd_create, MemfdFlags};
+    use std::os::fd::AsRawFd;
+
+    let fd = memfd_create(name, MemfdFlags::CLOEXEC).ok()?;
+    /* Our own pid rather than `self`, which would be the editor's once it opens the path */
+    let path = format!("/proc/{}/fd/{}", std::process::id(), fd.as_raw_fd());
+    Some((PathBuf::from(path), fs::File::from(fd)))
+}
+
+/* Only Linux has memory files, so the buffer goes on disk */
+#[cfg(not(all(feature = "memfd", target_os = "linux")))]
+fn memory_file(_name: &str) -> Option<(PathBuf, fs::File)> {
+    None
+}
+
+/* Makes a buffer readable and writable by the current user only */
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+/* Makes a buffer read-only for everyone, while leaving it deletable */
+#[cfg(unix)]
+fn make_read_only(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o400))
+}
+
+/* Elsewhere a read-only file can't be deleted, so it is left writable */
+#[cfg(not(unix))]
+fn make_read_only(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/* Other platforms don't have Unix permissions to restrict */
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/* Creates a file only the current user can read in the first default Scrawl directory that can be written to, for seeding from a writer without a buffer file */
+fn scratch_file(name: &str) -> io::Result<(PathBuf, fs::File)> {
+    for dir in scrawl_temp_dirs() {
+        if let Ok(file) = create_temp_file(&dir, name, true) {
+            let path = dir.join(name);
+            restrict_permissions(&path)?;
+            return Ok((path, file));
+        }
     }
+    Err(io::Error::other("no temporary directory can be written to"))
+}
 
-    /* Returns a different struct, consumes the Editor instead of returning a
-       reference; enforces a certain builder grammar.
-    */
-    /// Specify which editor should be opened instead of the user's default.
-    pub fn editor<S: AsRef<OsStr>>(self, editor: S) -> Editor<SpecificEditorState> {
-        Editor {
-            extension: self.extension,
-            state: SpecificEditorState {
-                editor: OsString::from(editor.as_ref()),
-                args: None,
-            },
+/* Whether the file at `path`, `len` bytes long, ends in a newline */
+fn ends_with_newline(path: &Path, len: u64) -> io::Result<bool> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(len.saturating_sub(1)))?;
+    let mut last = [0];
+    file.read_exact(&mut last)?;
+    Ok(last == *b"\n")
+}
+
+/* Every default Scrawl directory a buffer may have been created in, including the fallbacks */
+fn scrawl_temp_dirs() -> Vec<PathBuf> {
+    temp_dir_candidates()
+        .into_iter()
+        .map(|base| base.join(SCRAWL_TEMP_DIR))
+        .collect()
+}
+
+/* The entries of every default Scrawl directory that exists */
+fn temp_dir_entries() -> Result<Vec<fs::DirEntry>, ScrawlError> {
+    let mut entries = Vec::new();
+    for dir in scrawl_temp_dirs() {
+        match fs::read_dir(&dir) {
+            Ok(listed) => entries.extend(listed.collect::<Result<Vec<_>, _>>()?),
+            /* Nothing has been created there yet, or it isn't a directory at all */
+            Err(_) if !dir.is_dir() => continue,
+            Err(e) => return Err(e.into()),
         }
     }
+    Ok(entries)
+}
 
-    /// Opens the user's editor.
-    pub fn open(&mut self, contents: Contents) -> Result<Reader, Box<dyn Error>> {
-        /* Create a temporary file to use as a buffer */
-        let path = self.create_buffer_file(contents)?;
+/* Generates a file name no other buffer will have */
+fn unique_file_name(ext: &str) -> String {
+    let (ts, process_id, i) = unique_parts();
+    /* e.g. 1674864208_123_17.txt */
+    format!("{ts}_{process_id}_{i}{ext}")
+}
 
-        self.get_editor_programs()
-            .iter()
-            .find(|e| Command::new(e).arg(&path).status().is_ok())
-            .ok_or("Could not find a text editing program")?;
+/* Like unique_file_name, with a timestamp that can be read at a glance */
+fn readable_file_name(ext: &str) -> String {
+    let (ts, process_id, i) = unique_parts();
+    /* e.g. 2023-01-28T00-03-28_123_17.txt */
+    format!("{}_{process_id}_{i}{ext}", readable_timestamp(ts))
+}
+
+/* The seconds since the epoch, process ID and per-process counter that keep buffer names apart */
+fn unique_parts() -> (u64, u32, usize) {
+    let i = TEMP_FILE_COUNT.fetch_add(1, Ordering::SeqCst);
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or_else(|_| 0, |v| v.as_secs());
+    (ts, std::process::id(), i)
+}
+
+/* Formats seconds since the epoch as a UTC date and time, with dashes for colons so it works in file names */
+fn readable_timestamp(secs: u64) -> String {
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
 
-        Ok(Reader { path })
+    /* Civil date from days since 1970-01-01, after Howard Hinnant's civil_from_days */
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}-{minute:02}-{second:02}")
+}
+
+/* Extensions are stored with their leading dot */
+fn dotted(ext: &str) -> String {
+    match ext {
+        "" => String::new(),
+        ext if ext.starts_with('.') => ext.into(),
+        ext => format!(".{ext}"),
     }
+}
 
-    /// Opens a file for editing in the User's editor.
-    pub fn edit<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader, Box<dyn Error>> {
-        self.get_editor_programs()
-            .iter()
-            .find(|e| Command::new(e).arg(path.as_ref()).status().is_ok())
-            .ok_or("Could not find a text editing program")?;
+/* Stands in for the user when testing, see the crate level docs */
+fn apply_test_replacement(path: &Path) -> io::Result<bool> {
+    match env::var_os(TEST_REPLACEMENT_VAR) {
+        Some(replacement) => fs::write(path, replacement.as_encoded_bytes()).map(|_| true),
+        None => Ok(false),
+    }
+}
 
-        Ok(Reader {
-            path: path.as_ref().into(),
+/* Replaces the tokens in an argument with the buffer's `path` (`{}` or `{path}`, as `path_arg` for positioning), file `{name}`, `{ext}` and `{dir}`, if it has any. Anything else in braces is left as it is */
+fn fill_file_arg(arg: &OsStr, path: &Path, path_arg: &Path) -> Option<OsString> {
+    let arg = arg
+        .to_str()
+        .filter(|a| FILE_TOKENS.iter().any(|token| a.contains(token)))?;
+    let mut filled = OsString::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        filled.push(&rest[..start]);
+        rest = &rest[start..];
+        let Some(token) = FILE_TOKENS.iter().find(|token| rest.starts_with(*token)) else {
+            filled.push("{");
+            rest = &rest[1..];
+            continue;
+        };
+        filled.push(match *token {
+            "{name}" => path.file_name().unwrap_or_default(),
+            "{ext}" => path.extension().unwrap_or_default(),
+            "{dir}" => path.parent().map_or(OsStr::new(""), Path::as_os_str),
+            _ => path_arg.as_os_str(),
+        });
+        rest = &rest[token.len()..];
+    }
+    filled.push(rest);
+    Some(filled)
+}
+
+/* Whether an argument says where the buffer's path goes */
+fn places_path(arg: &OsStr) -> bool {
+    arg.to_str()
+        .is_some_and(|a| a.contains("{}") || a.contains("{path}"))
+}
+
+/* Persists a directory's entries (i.e. a newly created file). Best effort, not every platform or filesystem supports it */
+#[cfg(unix)]
+fn sync_dir(dir: &Path) {
+    let _ = fs::File::open(dir).and_then(|d| d.sync_all());
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) {}
+
+/* Whether `program` can be found, logging the answer */
+fn is_installed(program: &OsStr) -> bool {
+    let found = find_program(program);
+    match &found {
+        Some(path) => debug!("found {} at {}", program.to_string_lossy(), path.display()),
+        None => debug!("{} is not installed", program.to_string_lossy()),
+    }
+    found.is_some()
+}
+
+/* The OS's opener for files, and whether it waits for the application it opens; xdg-open doesn't */
+fn os_opener() -> (Program, bool) {
+    let (name, args, waits): (&str, &[&str], bool) = if cfg!(windows) {
+        ("cmd", &["/C", "start", "", "/WAIT"], true)
+    } else if cfg!(target_os = "macos") {
+        ("open", &["-W"], true)
+    } else {
+        ("xdg-open", &[], false)
+    };
+    let program = Program {
+        name: name.into(),
+        args: args.iter().map(OsString::from).collect(),
+    };
+    (program, waits)
+}
+
+/* The error for an editor path that exists but can't be run, which is more likely a mistake than a missing editor, so isn't skipped like one */
+fn not_executable(program: &Program) -> Option<ScrawlError> {
+    let path = Path::new(&program.name);
+    (path.components().count() > 1 && path.is_file() && !is_executable(path)).then(|| {
+        ScrawlError::FailedToOpenEditor(format!(
+            "{}: the file is not executable",
+            program.name.to_string_lossy()
+        ))
+    })
+}
+
+/* The error for when none of `programs` is installed */
+fn not_found(programs: Vec<Program>) -> ScrawlError {
+    ScrawlError::EditorNotFound(programs.into_iter().map(|p| p.name).collect())
+}
+
+/* Resolves a program the way the OS would when spawning it, without spawning it */
+fn find_program(program: &OsStr) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.as_os_str().is_empty() {
+        return None;
+    }
+
+    /* Paths like ./edit.sh or /usr/bin/vim are not looked up on the PATH */
+    if program.components().count() > 1 {
+        return is_executable(program).then(|| program.into());
+    }
+
+    let extensions: Vec<OsString> = match env::var_os("PATHEXT") {
+        Some(exts) if cfg!(windows) => env::split_paths(&exts)
+            .map(|ext| ext.into_os_string())
+            .collect(),
+        _ => vec![],
+    };
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        let candidate = dir.join(program);
+        if is_executable(&candidate) {
+            return Some(candidate);
+        }
+        extensions.iter().find_map(|ext| {
+            let mut with_ext = candidate.clone().into_os_string();
+            with_ext.push(ext);
+            let with_ext = PathBuf::from(with_ext);
+            is_executable(&with_ext).then_some(with_ext)
         })
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path).is_ok_and(|m| m.is_file())
+}
+
+/* What to do once the user has saved nothing */
+/// Decides what happens when the user saves a buffer empty (or only whitespace), for `Editor::on_empty` and `edit_each`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum OnEmpty {
+    /// Keep the empty value like any other.
+    #[default]
+    Keep,
+    /// For `edit_each`, leave the item out of the results and move on to the next one. A single buffer has nothing to move on to, so it fails like `Error`.
+    Skip,
+    /// For `edit_each`, stop editing, returning only the items edited so far. A single buffer fails like `Error`.
+    Stop,
+    /// Fail with `ScrawlError::EmptyBuffer`, e.g. to abort like `git commit` does.
+    Error,
+    /// Open the editor again with `message` in the buffer, until the user saves something else. The message's lines are taken out again each time the editor closes.
+    Reopen {
+        /// Guidance for the user, e.g. "# The description can't be empty". Write it as a comment for the buffer's format if it should look like one.
+        message: String,
+    },
+}
+
+/* How line endings are written back out */
+/// The line endings a Reader uses when it writes the buffer out with `to_file`, `append_to_file` or `save_to_dir`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NewlineStyle {
+    /// Keep whatever line endings the editor saved.
+    #[default]
+    Preserve,
+    /// Write `\n` line endings.
+    Lf,
+    /// Write `\r\n` line endings.
+    CrLf,
+}
+
+/* Converts the line endings in `bytes` to `style` */
+fn convert_newlines(bytes: Vec<u8>, style: NewlineStyle) -> Vec<u8> {
+    if style == NewlineStyle::Preserve {
+        return bytes;
+    }
+    let mut converted = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().peekable();
+    while let Some(&b) = iter.next() {
+        match b {
+            b'\r' if iter.peek() == Some(&&b'\n') => (),
+            b'\n' if style == NewlineStyle::CrLf => converted.extend_from_slice(b"\r\n"),
+            _ => converted.push(b),
+        }
     }
+    converted
 }
 
-/// A variant of the Editor struct with a specific command and arguments for the text editor instead of the user's defaults. This struct is created when an editor is specified.
-#[derive(Debug)]
-pub struct SpecificEditorState {
-    args: Option<Vec<OsString>>,
-    editor: OsString,
+/* Operating systems an editor can be chosen for */
+/// Operating systems, for `editor_for`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Os {
+    /// Linux
+    Linux,
+    /// macOS
+    MacOs,
+    /// Windows
+    Windows,
+    /// FreeBSD
+    FreeBsd,
+    /// OpenBSD
+    OpenBsd,
+    /// NetBSD
+    NetBsd,
 }
-impl EditorState for SpecificEditorState {}
 
-impl Editor<SpecificEditorState> {
-    /// Add arguments that you want to be used when the command is run. The first argument is always the file being used as the buffer. Requires that a specific editor has been set.
-    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
-        self.state
-            .args
-            .get_or_insert(vec![])
-            .push(OsString::from(arg.as_ref()));
-        self
+impl Os {
+    /* Whether this is the OS we're running on */
+    fn is_current(self) -> bool {
+        let name = match self {
+            Os::Linux => "linux",
+            Os::MacOs => "macos",
+            Os::Windows => "windows",
+            Os::FreeBsd => "freebsd",
+            Os::OpenBsd => "openbsd",
+            Os::NetBsd => "netbsd",
+        };
+        name == env::consts::OS
     }
+}
 
-    /// Specify which extension should be used on the temporary file (often used by text editors to infer syntax highlighting).
-    pub fn ext<S: AsRef<str>>(&mut self, ext: S) -> &mut Self {
-        self.extension = ext.as_ref().into();
-        self
+/* When Scrawl adds editor-specific flags of its own */
+/// Decides whether Scrawl adds editor-specific flags to the editor's command line. Flags for options the caller set, such as `read_only` or `cursor_marker`, are added unless this is `Never`; the ones Scrawl picks by itself, such as Vim's `-o` to split the windows for `open_with_reference`, depend on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlagInjection {
+    /// Add Scrawl's own flags unless `NO_COLOR` is set or stdout isn't a terminal, where the user's own editor setup is left alone.
+    #[default]
+    Auto,
+    /// Always add them.
+    Always,
+    /// Never add any, not even for the options the caller set.
+    Never,
+}
+
+/* Shared by each EditorState's edit_each */
+fn edit_each_with<S, I>(
+    editor: &mut Editor<S>,
+    items: I,
+    on_empty: OnEmpty,
+    open: fn(&mut Editor<S>, Contents) -> Result<Reader, ScrawlError>,
+) -> Result<Vec<String>, ScrawlError>
+where
+    S: EditorState,
+    I: IntoIterator<Item = String>,
+{
+    /* Failing and reopening are left to each `open`, the rest are decided here */
+    let per_open = match &on_empty {
+        OnEmpty::Error | OnEmpty::Reopen { .. } => on_empty.clone(),
+        _ => OnEmpty::Keep,
+    };
+    let kept = std::mem::replace(&mut editor.on_empty, per_open);
+    let mut edited = Vec::new();
+    let mut result = Ok(());
+    for item in items {
+        let value = match open(editor, Contents::FromOwnedString(item.into_bytes()))
+            .and_then(|reader| reader.to_string())
+        {
+            Ok(value) => value,
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        };
+        if value.trim().is_empty() {
+            match on_empty {
+                OnEmpty::Skip => continue,
+                OnEmpty::Stop => break,
+                _ => (),
+            }
+        }
+        edited.push(value);
     }
+    editor.on_empty = kept;
+    result.map(|_| edited)
+}
 
-    /// Opens the user's editor.
-    pub fn open(&mut self, contents: Contents) -> Result<Reader, Box<dyn Error>> {
-        /* Create a temporary file to use as a buffer */
-        let path = self.create_buffer_file(contents)?;
+/* Helpers for Editor::wait_on_close */
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
 
-        /* Open the editor, store a handle to the child process */
-        Command::new(&self.state.editor)
-            .arg(&path)
-            .args(self.state.args.as_ref().unwrap_or(&vec![]))
-            .status()?;
+/* Waits for the buffer to be saved and then left alone for `quiet_period`, or for `limit` to pass, whichever comes first */
+fn wait_for_quiet(
+    path: &Path,
+    launched_at: Option<SystemTime>,
+    seed: Option<&[u8]>,
+    quiet_period: Duration,
+    limit: Duration,
+) {
+    let poll = (quiet_period / 10).clamp(Duration::from_millis(10), Duration::from_millis(250));
+    let started = Instant::now();
+    let expired = || {
+        let expired = started.elapsed() >= limit;
+        if expired {
+            warn!("gave up waiting on {} after {limit:?}", path.display());
+        }
+        expired
+    };
 
-        Ok(Reader { path })
+    /* Wait for the first save; on filesystems with coarse timestamps it may not change the mtime, but it will change the contents */
+    let mut last = modified_time(path);
+    while last == launched_at && seed.is_none_or(|seed| fs::read(path).is_ok_and(|now| now == seed))
+    {
+        if expired() {
+            return;
+        }
+        thread::sleep(poll);
+        last = modified_time(path);
     }
 
-    /// Opens a file for editing in the User's editor.
-    pub fn edit<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader, Box<dyn Error>> {
-        /* Open the editor, store a handle to the child process */
-        Command::new(&self.state.editor)
-            .arg(path.as_ref())
-            .args(self.state.args.as_ref().unwrap_or(&vec![]))
-            .status()?;
+    /* Then wait for the saves to stop */
+    let mut quiet_since = Instant::now();
+    while quiet_since.elapsed() < quiet_period {
+        if expired() {
+            return;
+        }
+        thread::sleep(poll);
+        let now = modified_time(path);
+        if now != last {
+            last = now;
+            quiet_since = Instant::now();
+        }
+    }
+}
 
-        Ok(Reader {
-            path: path.as_ref().into(),
+/* Sweeps the Scrawl temp directory for buffers leaked by crashed processes */
+/// Deletes temporary buffers that have not been modified within `max_age`, returning how many were removed. Only the default `xvrqt_scrawl` directories are swept: the one in the temporary directory, and those in the fallback locations used when it can't be written to.
+///
+/// Buffers are normally removed when their `Reader` is dropped, but a process that panics or is killed while the editor is open leaks its buffer. Files are judged by their last modification time, so this races with other Scrawl instances: a buffer whose editor has been open, unsaved, for longer than `max_age` is indistinguishable from a leaked one and will be deleted. Pick a `max_age` comfortably longer than any editing session.
+pub fn cleanup_stale(max_age: Duration) -> Result<usize, ScrawlError> {
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in temp_dir_entries()? {
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            /* Removed by its own Reader since the directory was listed */
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        /* Files with an mtime in the future are left alone */
+        let is_stale = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+
+        /* Another process may have removed it in the meantime */
+        if is_stale && fs::remove_file(entry.path()).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Lists every buffer in the default `xvrqt_scrawl` directories, whether or not it is still in use, e.g. to find leaked buffers. Like `cleanup_stale`, this includes the directories in the fallback locations. Nothing outside those directories is looked at, and neither are their subdirectories.
+pub fn list_temp_files() -> Result<Vec<PathBuf>, ScrawlError> {
+    let mut files = Vec::new();
+    for entry in temp_dir_entries()? {
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_file() => files.push(entry.path()),
+            Ok(_) => (),
+            /* Removed by its own Reader since the directory was listed */
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(files)
+}
+
+/// Deletes every buffer `list_temp_files` finds, returning how many were removed. Unlike `cleanup_stale` this doesn't spare buffers that are in use, so an editor Scrawl has open loses its buffer; it is meant for a `--clean` style command, not routine use.
+pub fn clear_temp_files() -> Result<usize, ScrawlError> {
+    let mut removed = 0;
+    for file in list_temp_files()? {
+        /* Another process may have removed it in the meantime */
+        if fs::remove_file(file).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Returns the editor that `open` would use, found the same way (`SCRAWL_EDITOR`, `VISUAL`, `EDITOR`, then the built-in list, checked against `PATH`), without creating a buffer or launching anything. `None` if no editor is installed. Any arguments from the environment variable are left off, e.g. `code` for `EDITOR="code --wait"`.
+pub fn detect() -> Option<OsString> {
+    new().resolve_editor().ok().map(|program| program.name)
+}
+
+/* When stdin is piped there's nobody to open an editor for, so its contents stand in for the user's edit */
+pub(crate) fn read_piped_stdin(seed: &[u8]) -> Result<Option<Reader>, ScrawlError> {
+    /* The test replacement still goes through the editor path */
+    if io::stdin().is_terminal() || env::var_os(TEST_REPLACEMENT_VAR).is_some() {
+        return Ok(None);
+    }
+
+    let mut captured = Vec::new();
+    io::stdin().read_to_end(&mut captured)?;
+    Ok(Some(Reader {
+        path: PathBuf::new(),
+        temporary: false,
+        captured: Some(captured),
+        editor: STDIN_EDITOR.into(),
+        args: vec![],
+        status: None,
+        extension: String::new(),
+        newline: NewlineStyle::Preserve,
+        region: None,
+        encoding: None,
+        max_size: None,
+        on_read: None,
+        shared: None,
+        seed: Some(seed.to_vec()),
+        cache: Mutex::new(None),
+        stream: None,
+        relaunch: None,
+    }))
+}
+
+/* An editor program, with any arguments that came along with it, e.g. from EDITOR="code --wait" */
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Program {
+    name: OsString,
+    args: Vec<OsString>,
+}
+
+impl Program {
+    /// Splits an environment variable's value into a program and its arguments, like a shell would. Returns `None` if it is blank.
+    fn parse(value: &OsStr) -> Option<Self> {
+        /* A value that isn't UTF-8 can't be split, so treat it all as the program */
+        let mut words = match value.to_str() {
+            Some(value) => shell_split(value).into_iter().map(OsString::from),
+            None => return Some(Program::from(value)),
+        };
+        Some(Program {
+            name: words.next()?,
+            args: words.collect(),
         })
     }
 }
 
-/* User chooses a variant with which to seed the editor contents */
-/// Enum for what to seed the editor's buffer with.
-#[allow(missing_debug_implementations)]
-pub enum Contents<'a> {
-    /// Editor opens an empty file.
-    Empty,
-    /// Editor opens a file with the contents of a String.
-    FromString(&'a dyn AsRef<[u8]>),
-    /// Editor opens a file with the copied contents of a file at specified path.
-    FromFile(&'a dyn AsRef<Path>),
+impl<S: AsRef<OsStr> + ?Sized> From<&S> for Program {
+    fn from(name: &S) -> Self {
+        Program {
+            name: name.as_ref().into(),
+            args: vec![],
+        }
+    }
 }
 
-/// After the user closes their editor, it transforms into a Reader object where the input can be retrieved.
+/* Splits on whitespace, respecting quotes and (except on Windows, where it separates paths) backslash escapes */
+fn shell_split(value: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\\' if !cfg!(windows) => word.get_or_insert_with(String::new).extend(chars.next()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if !cfg!(windows) => match chars.next() {
+                            Some(e @ ('"' | '\\' | '$' | '`')) => word.push(e),
+                            Some(e) => {
+                                word.push('\\');
+                                word.push(e);
+                            }
+                            None => word.push('\\'),
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/* An editor that has been started, but not waited on */
 #[derive(Debug)]
-pub struct Reader {
-    path: PathBuf,
+struct Started {
+    child: Child,
+    program: Program,
+    args: Vec<OsString>,
+    stderr: Option<StderrTee>,
+    relaunch: Relaunch,
 }
 
-impl Reader {
-    /// Read to a vector of bytes.
-    pub fn read(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        Ok(fs::read(&self.path)?)
+/* A caller's `command_hook`, which has nothing to print; shared with the Readers, so `reedit` can apply it again */
+#[derive(Clone)]
+struct CommandHook(Arc<Mutex<HookFn>>);
+
+type HookFn = dyn FnMut(&mut Command) + Send;
+
+impl CommandHook {
+    fn apply(&self, command: &mut Command) {
+        let mut hook = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        (*hook)(command);
+    }
+}
+
+impl fmt::Debug for CommandHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CommandHook")
     }
+}
+
+/* A caller's `on_read`, shared by every Reader the Editor makes */
+#[derive(Clone)]
+struct ReadHook(Arc<dyn Fn(String) -> String + Send + Sync>);
+
+impl fmt::Debug for ReadHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ReadHook")
+    }
+}
+
+/* A caller's `on_fallback`, told when the user's editor couldn't be used */
+struct FallbackHook(Box<FallbackFn>);
 
-    /// Returns the buffer as a String.
-    pub fn to_string(&self) -> Result<String, Box<dyn Error>> {
-        Ok(fs::read_to_string(&self.path)?)
+/* Called with the editor the user wanted and the one used instead */
+type FallbackFn = dyn FnMut(&OsStr, &OsStr) + Send;
+
+impl fmt::Debug for FallbackHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FallbackHook")
     }
+}
 
-    /// Returns the buffer as a BufReader.
-    pub fn to_bufreader(&self) -> Result<BufReader<fs::File>, Box<dyn Error>> {
-        Ok(BufReader::new(fs::File::open(&self.path)?))
+/* What was run to edit a buffer */
+#[derive(Debug)]
+struct Launched {
+    editor: OsString,
+    args: Vec<OsString>,
+    status: Option<ExitStatus>,
+    stderr: String,
+    relaunch: Option<Relaunch>,
+}
+
+/* The command line an editor was spawned with, for `Reader::reedit` to run again */
+#[derive(Debug, Clone)]
+struct Relaunch {
+    program: OsString,
+    args: Vec<OsString>,
+    dir: Option<PathBuf>,
+    envs: Vec<(OsString, Option<OsString>)>,
+    capture: bool,
+    hook: Option<CommandHook>,
+}
+
+impl Relaunch {
+    /* Copies what `command` is set to run, before the `hook` that it is about to be given */
+    fn of(command: &Command, capture: bool, hook: Option<CommandHook>) -> Self {
+        Relaunch {
+            program: command.get_program().into(),
+            args: command.get_args().map(OsString::from).collect(),
+            dir: command.get_current_dir().map(PathBuf::from),
+            envs: command
+                .get_envs()
+                .map(|(name, value)| (name.into(), value.map(OsString::from)))
+                .collect(),
+            capture,
+            hook,
+        }
     }
 
-    /// Saves the contents to a file at the specified path.
-    pub fn to_file<P: AsRef<Path>>(&self, path: &P) -> Result<u64, Box<dyn Error>> {
-        Ok(fs::copy(&self.path, path.as_ref())?)
+    /* Runs the editor again and waits for it to exit, with what it wrote to stderr if that is being kept */
+    fn run(&self) -> Result<(ExitStatus, String), ScrawlError> {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        if let Some(dir) = &self.dir {
+            command.current_dir(dir);
+        }
+        for (name, value) in &self.envs {
+            match value {
+                Some(value) => command.env(name, value),
+                None => command.env_remove(name),
+            };
+        }
+        if self.capture {
+            command.stderr(Stdio::piped());
+        }
+        if let Some(hook) = &self.hook {
+            hook.apply(&mut command);
+        }
+        let mut child = command.spawn().map_err(|e| open_error(&self.program, e))?;
+        let tee = child.stderr.take().filter(|_| self.capture).map(tee_stderr);
+        let status = child.wait()?;
+        let stderr = tee.map(|tee| String::from_utf8_lossy(&tee.finish()).into_owned());
+        Ok((status, stderr.unwrap_or_default()))
     }
 }
 
-/* Delete our temporary file to clean up */
-impl Drop for Reader {
-    fn drop(&mut self) {
-        let mut temp_dir_path = env::temp_dir();
-        temp_dir_path.push(SCRAWL_TEMP_DIR);
+/* How the files opened alongside the buffer are shown, by editors that can be told */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layout {
+    /* Side by side, for `open_with_reference` */
+    Split,
+    /* One tab each, for `edit_files` */
+    Tabs,
+}
+
+/* Editors whose command line flags Scrawl knows, for options like read_only */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditorKind {
+    Vim,
+    Nano,
+    Emacs,
+    Micro,
+    VsCode,
+    Sublime,
+}
+
+impl EditorKind {
+    /// Guesses which editor a program is from its file name, e.g. `/usr/bin/nvim` is Vim.
+    fn detect(program: &OsStr) -> Option<Self> {
+        let name = Path::new(program).file_stem()?.to_str()?;
+        match name {
+            "vim" | "vi" | "nvim" | "neovim" | "gvim" | "view" => Some(EditorKind::Vim),
+            "nano" => Some(EditorKind::Nano),
+            "emacs" | "emacsclient" => Some(EditorKind::Emacs),
+            "micro" => Some(EditorKind::Micro),
+            "code" | "code-insiders" | "codium" => Some(EditorKind::VsCode),
+            "subl" | "sublime_text" => Some(EditorKind::Sublime),
+            _ => None,
+        }
+    }
 
-        /* Only clean up files we created */
-        if self.path.starts_with(temp_dir_path) {
-            let _ = fs::remove_file(&self.path);
+    /// Flags that open a file without allowing edits.
+    fn read_only_flags(self) -> &'static [&'static str] {
+        match self {
+            EditorKind::Vim => &["-R"],
+            EditorKind::Nano => &["-v"],
+            EditorKind::Emacs | EditorKind::Micro | EditorKind::VsCode | EditorKind::Sublime => &[],
+        }
+    }
+
+    /// Flags that show every file given in a window or tab of its own.
+    fn layout_flags(self, layout: Layout) -> &'static [&'static str] {
+        match (self, layout) {
+            (EditorKind::Vim, Layout::Split) => &["-o"],
+            (EditorKind::Vim, Layout::Tabs) => &["-p"],
+            _ => &[],
+        }
+    }
+
+    /// Flags for a fast startup that leaves no swap or backup files behind.
+    fn quiet_flags(self) -> &'static [&'static str] {
+        match self {
+            EditorKind::Vim => &["-n"],
+            EditorKind::Nano => &["-I"],
+            EditorKind::Emacs => &["-Q"],
+            EditorKind::Micro => &["-backup", "false"],
+            EditorKind::VsCode | EditorKind::Sublime => &[],
+        }
+    }
+
+    /// Flags that open the file in a new window.
+    fn new_window_flags(self) -> &'static [&'static str] {
+        match self {
+            EditorKind::VsCode => &["--new-window"],
+            EditorKind::Sublime => &["-n"],
+            EditorKind::Vim | EditorKind::Nano | EditorKind::Emacs | EditorKind::Micro => &[],
+        }
+    }
+
+    /// Flags that start the cursor at a position. VS Code and Sublime Text take the position on the path instead, see `goto_path`.
+    fn goto_flags(self, at: Position) -> Vec<OsString> {
+        let Position { line, column } = at;
+        let flag = match self {
+            EditorKind::Sublime => return vec![],
+            EditorKind::VsCode => String::from("--goto"),
+            EditorKind::Vim => format!("+call setcursorcharpos({line}, {column})"),
+            EditorKind::Nano => format!("+{line},{column}"),
+            EditorKind::Emacs | EditorKind::Micro => format!("+{line}:{column}"),
+        };
+        vec![flag.into()]
+    }
+
+    /// Flags that open with the text from `start` to `end` selected, for editors that can.
+    fn select_flags(self, start: Position, end: Position) -> Option<Vec<OsString>> {
+        match self {
+            EditorKind::Vim => Some(vec![format!(
+                "+call setcharpos(\"'<\", [0, {}, {}, 0]) | call setcharpos(\"'>\", [0, {}, {}, 0]) | normal! gv",
+                start.line, start.column, end.line, end.column
+            )
+            .into()]),
+            _ => None,
+        }
+    }
+
+    /// The path to pass for the cursor to start at a position, e.g. `file.txt:3:5` for VS Code.
+    fn goto_path(self, path: &Path, at: Position) -> PathBuf {
+        match self {
+            EditorKind::VsCode | EditorKind::Sublime => {
+                let mut positioned = path.as_os_str().to_owned();
+                positioned.push(format!(":{}:{}", at.line, at.column));
+                positioned.into()
+            }
+            _ => path.into(),
+        }
+    }
+}
+
+/* A 1-based line and column (in characters) in the buffer */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+/* Trait that is used to keep track which state the Editor is in */
+/// Used to keep track of which state the Editor struct is in while it's constructed.
+pub trait EditorState {}
+
+/* These options are available to all states of the Editor */
+impl<S: EditorState> Editor<S> {
+    /// The extension set with `ext()`, including the leading dot, or `.txt` (`.bin` in binary mode) if none was set. A `Contents::FromFile` buffer uses its seed file's extension instead when none was set, as long as that extension is UTF-8.
+    pub fn extension(&self) -> &str {
+        self.extension
+            .as_deref()
+            .unwrap_or_else(|| self.default_extension())
+    }
+
+    /// Name of the directory, inside the system's temporary directory, that buffers are created in. Defaults to `xvrqt_scrawl`. It is created on first use, which is safe to do from several threads or processes at once.
+    ///
+    /// If the temporary directory can't be written to, the directory is created in the first of `$TMPDIR`, `$XDG_RUNTIME_DIR`, the current directory and the user's home directory that can be. Only if none can is `ScrawlError::FailedToCreateTempfile` returned, listing where it tried.
+    ///
+    /// Buffers in a custom subdirectory are deleted along with their Reader, just like the default ones.
+    pub fn temp_subdir<N: AsRef<str>>(&mut self, name: N) -> &mut Self {
+        self.temp_subdir = name.as_ref().into();
+        self
+    }
+
+    /// Create buffers in a hidden `.scrawl` directory inside the project at `root`, instead of the temporary directory, so that an editor's language server and project plugins treat the buffer as part of that project. The buffer is still deleted along with its Reader, and so is the `.scrawl` directory once it is empty. Consider adding `.scrawl` to the project's ignore files.
+    pub fn in_project<P: AsRef<Path>>(&mut self, root: P) -> &mut Self {
+        self.project = Some(root.as_ref().into());
+        self
+    }
+
+    /// Whether a nested `temp_subdir` like `"tool/drafts"` is created along with any of its missing parents. When false, only the last directory is created, so a missing parent fails fast with `ScrawlError::FailedToCreateTempfile`. Defaults to true.
+    pub fn create_temp_parents(&mut self, parents: bool) -> &mut Self {
+        self.create_temp_parents = parents;
+        self
+    }
+
+    /// Name buffers with a readable UTC timestamp, like `2024-01-27T15-30-00_1234_0.txt`, instead of seconds since the epoch (`1706369400_1234_0.txt`), to make it easier to tell which buffer is which in the temporary directory. The process ID and counter still keep the names unique. Defaults to false.
+    pub fn debug_filenames(&mut self, readable: bool) -> &mut Self {
+        self.debug_filenames = readable;
+        self
+    }
+
+    /// Create the file passed to `edit` (and any missing parent directories) if it does not exist yet, instead of leaving that up to the editor.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Open the buffer in the editor's read-only/view mode, e.g. to preview content. Supported for vim (`-R`) and nano (`-v`); other editors open the buffer normally. See `inject_flags`.
+    pub fn read_only(&mut self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Ask a GUI editor to open the buffer in a window of its own, rather than in whichever project window it has open, so it's clear which file is being edited. Supported for VS Code (`--new-window`) and Sublime Text (`-n`); terminal editors are unaffected. See `inject_flags`.
+    pub fn new_window(&mut self, new_window: bool) -> &mut Self {
+        self.new_window = new_window;
+        self
+    }
+
+    /// Fail with `ScrawlError::NotInteractive` before creating a buffer, instead of opening an editor nobody can use, if stdin or stdout isn't a terminal, e.g. in CI or a cron job. Doesn't apply to `pipe_mode`, `SCRAWL_TEST_REPLACEMENT` or `transform`, which don't need a user. Defaults to false.
+    pub fn require_interactive(&mut self, require: bool) -> &mut Self {
+        self.require_interactive = require;
+        self
+    }
+
+    /// Start the editor with as little as possible, for quick throwaway buffers: vim skips its swap file (`-n`), nano ignores its rc files (`-I`), emacs skips the user's init files (`-Q`) and micro makes no backups (`-backup false`). Other editors are unaffected. See `inject_flags`.
+    pub fn quiet(&mut self, quiet: bool) -> &mut Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Run the editor as a filter: `open` writes the seed contents to its stdin and captures its stdout, instead of passing it a file. No temporary file is created; the Reader holds the output in memory. `edit` is unaffected.
+    pub fn pipe_mode(&mut self, pipe_mode: bool) -> &mut Self {
+        self.pipe_mode = pipe_mode;
+        self
+    }
+
+    /// Treat the buffer as opaque bytes rather than UTF-8 text: nothing is done to the contents on their way into or out of the editor (such as removing the `cursor_marker`), and the default extension becomes `.bin`. Read the result with `Reader::read_exact_bytes`.
+    pub fn binary(&mut self, binary: bool) -> &mut Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Convert the buffer's line endings when the Reader writes it to a file, e.g. so a tool that only writes LF files never picks up the editor's CRLFs. Defaults to `NewlineStyle::Preserve`. Ignored in binary mode.
+    pub fn newline(&mut self, style: NewlineStyle) -> &mut Self {
+        self.newline = style;
+        self
+    }
+
+    /// Present the buffer to the editor in another encoding, given as a WHATWG label like `"shift_jis"` or `"latin1"`: the seed contents are transcoded from UTF-8 before the editor sees them, and the Reader decodes the buffer back into UTF-8. Seed contents that aren't UTF-8 are assumed to be in the encoding already, and characters it can't represent are written as HTML character references, as `encoding_rs` does. An unknown label fails with `ScrawlError::UnknownEncoding` when the editor is opened. Defaults to UTF-8; ignored in binary mode.
+    #[cfg(feature = "encoding_rs")]
+    pub fn encoding(&mut self, label: &str) -> &mut Self {
+        self.encoding = Some(label.into());
+        self
+    }
+
+    /// Surround the seeded contents with `header` and `footer`, e.g. for instructions, each kept apart from the contents by a sentinel line. The full text is what the editor shows, but the Reader only returns what is between the sentinels. If the user deletes a sentinel, the contents run from the start or to the end of the buffer instead. Ignored in binary mode and `pipe_mode`. `to_bufreader` and `with_path` still see the whole file.
+    pub fn wrap<H: AsRef<str>, F: AsRef<str>>(&mut self, header: H, footer: F) -> &mut Self {
+        self.wrap = Some((header.as_ref().into(), footer.as_ref().into()));
+        self
+    }
+
+    /// The lines that separate the contents from the `wrap` header and footer. Defaults to git-style scissors lines, `>8` above the contents and `8<` below.
+    pub fn sentinels<B: AsRef<str>, E: AsRef<str>>(&mut self, start: B, end: E) -> &mut Self {
+        self.sentinels = (start.as_ref().into(), end.as_ref().into());
+        self
+    }
+
+    /// Whether Scrawl may add editor-specific flags to the command line, see `FlagInjection`. By default the flags for options set on the Editor are always added, and the ones Scrawl picks by itself are left out when `NO_COLOR` is set or stdout isn't a terminal.
+    pub fn inject_flags(&mut self, injection: FlagInjection) -> &mut Self {
+        self.flag_injection = injection;
+        self
+    }
+
+    /// Refuse to read back a buffer larger than `bytes`: the Reader's read methods fail with `ScrawlError::BufferTooLarge` instead of loading it. The size is checked before the file is read. Unlimited by default.
+    pub fn max_size(&mut self, bytes: u64) -> &mut Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Decide what happens when the user saves the buffer empty (or only whitespace), see `OnEmpty`. Under `SCRAWL_TEST_REPLACEMENT` there is nobody to reopen it for, so `Reopen` fails like `Error`. Defaults to `OnEmpty::Keep`. Not applied in `pipe_mode`, or for `spawn`.
+    pub fn on_empty(&mut self, policy: OnEmpty) -> &mut Self {
+        self.on_empty = policy;
+        self
+    }
+
+    /// Fail with `ScrawlError::Aborted` if the user leaves a line reading exactly `sentinel` (e.g. `ABORT`) in the buffer, giving them a way to cancel from inside any editor, whatever its exit code. It is checked before `on_empty`, so a buffer holding only the sentinel is aborted, not reopened. Not applied in `pipe_mode`, or for `spawn`.
+    pub fn abort_on(&mut self, sentinel: &str) -> &mut Self {
+        self.abort_on = Some(sentinel.into());
+        self
+    }
+
+    /// Run `program` with `args` and then the buffer's path after the editor closes, before the Reader is made, e.g. `post_process("rustfmt", ["--edition", "2021"])` to format a snippet the user wrote. Fails with `ScrawlError::PostProcessFailed` if it can't be run or exits unsuccessfully. Not run in `pipe_mode`, or for `spawn`.
+    pub fn post_process<I, P>(&mut self, program: P, args: I) -> &mut Self
+    where
+        P: AsRef<OsStr>,
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        self.post_process = Some(Program {
+            name: program.as_ref().into(),
+            args: args.into_iter().map(|a| a.as_ref().into()).collect(),
+        });
+        self
+    }
+
+    /// Call `hook` on the editor's `Command` just before it is spawned, for process options Scrawl has no method for, such as `pre_exec` on Unix or creation flags on Windows. Scrawl has already added its own arguments by then, including the buffer's path, so any the hook adds come last. It is called every time an editor is started, and replaces any earlier hook. In `pipe_mode` the editor's stdin and stdout are set to pipes after the hook has run.
+    pub fn command_hook<F: FnMut(&mut Command) + Send + 'static>(&mut self, hook: F) -> &mut Self {
+        self.command_hook = Some(CommandHook(Arc::new(Mutex::new(hook))));
+        self
+    }
+
+    /// Run `f` over the text every time a Reader made by this Editor returns it as a String, and so in everything built on `to_string`.
+    pub fn on_read<F: Fn(String) -> String + Send + Sync + 'static>(&mut self, f: F) -> &mut Self {
+        self.on_read = Some(ReadHook(Arc::new(f)));
+        self
+    }
+
+    /// Keep the buffer in an anonymous memory file on Linux instead of a temporary file, falling back to a temporary file only the current user can read elsewhere.
+    #[cfg(feature = "memfd")]
+    pub fn memory_backed(&mut self, memory: bool) -> &mut Self {
+        self.memory_backed = memory;
+        self
+    }
+
+    /// Reuse one buffer file for every `open` on this Editor, re-seeding it each time instead of creating a new file, e.g. for an edit-validate-reopen loop. A new file is still made if the extension changes. Since each round overwrites the file, read a Reader before opening the next one. The file is deleted once the Editor and all of its Readers are dropped.
+    pub fn reuse_buffer(&mut self, reuse: bool) -> &mut Self {
+        self.reuse_buffer = reuse;
+        if !reuse {
+            self.reused = None;
+        }
+        self
+    }
+
+    /// For GUI editors that return before the user is done: after the editor exits, keep waiting until the buffer has been saved at least once and then left unchanged for `quiet_period`.
+    ///
+    /// This is a heuristic. It waits until `wait_on_close_limit` runs out if the user closes the editor without saving, and returns early if they pause for longer than `quiet_period` between saves. Prefer the editor's own wait flag (e.g. `code --wait`) where it works; terminal editors never need this.
+    pub fn wait_on_close(&mut self, quiet_period: Duration) -> &mut Self {
+        self.wait_on_close = Some(quiet_period);
+        self
+    }
+
+    /// The longest `wait_on_close` (and `open_with_os_default`, where it waits the same way) keeps waiting in all, so a user who never saves can't block the process forever. When it runs out, the buffer is read as it is, which is unchanged if it was never saved. Defaults to an hour.
+    pub fn wait_on_close_limit(&mut self, limit: Duration) -> &mut Self {
+        self.wait_on_close_limit = limit;
+        self
+    }
+
+    /// Start the cursor where `marker` (e.g. `<|>`) appears in the seeded contents. The marker is removed from the buffer before the editor opens. Supported for vim, nano, emacs and micro; other editors open at their usual position. See `inject_flags`.
+    pub fn cursor_marker<M: AsRef<str>>(&mut self, marker: M) -> &mut Self {
+        self.cursor_marker = Some(marker.as_ref().into());
+        self
+    }
+
+    /// Start the cursor at the very end of the buffer, e.g. so the user can add an entry to a journal straight away. If the buffer ends in a newline, that is just after the last character of the last line. A `cursor_marker` in the contents takes precedence. Supported for the same editors as `cursor_marker`; others open at their usual position. See `inject_flags`.
+    pub fn cursor_at_end(&mut self, at_end: bool) -> &mut Self {
+        self.cursor_at_end = at_end;
+        self
+    }
+
+    /// Open with the text from `start` to `end` selected, each a 1-based `(line, column)` with the column counted in characters, e.g. to point the user at a span they need to review. Vim starts in visual mode over it; vscode, nano, emacs and micro put the cursor at `start` instead. Takes precedence over `cursor_marker` and `cursor_at_end`. See `inject_flags`.
+    pub fn select(&mut self, start: (usize, usize), end: (usize, usize)) -> &mut Self {
+        let position = |(line, column)| Position { line, column };
+        self.selection = Some((position(start), position(end)));
+        self
+    }
+
+    /// Run the editor in `dir` instead of this process's current directory. Relative paths passed to `edit`, and relative editor paths like `./scripts/edit.sh`, are still resolved against this process's current directory.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Self {
+        self.current_dir = Some(dir.as_ref().into());
+        self
+    }
+
+    /// Configure the editor's standard input. Inherited from this process by default; the handle is used by the next editor launched.
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.stdin = Some(cfg.into());
+        self
+    }
+
+    /// Configure the editor's standard output. Inherited from this process by default; the handle is used by the next editor launched.
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.stdout = Some(cfg.into());
+        self
+    }
+
+    /// Configure the editor's standard error. Inherited from this process by default; the handle is used by the next editor launched.
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Self {
+        self.stderr = Some(cfg.into());
+        self
+    }
+}
+
+/* These function are available to all states of the Editor. Utility functions */
+impl<S: EditorState> Editor<S> {
+    /// Has `launch` open the editor on `path`, then wraps up the result in a Reader.
+    fn run<F>(
+        &mut self,
+        path: PathBuf,
+        temporary: bool,
+        mut launch: F,
+    ) -> Result<Reader, ScrawlError>
+    where
+        F: FnMut(&mut Self, &Path) -> Result<Launched, ScrawlError>,
+    {
+        /* Remember what the buffer started out as (nothing, if the file doesn't exist yet) */
+        let seed = fs::read(&path).ok();
+
+        let mut reopened: Option<String> = None;
+        loop {
+            let replaced = apply_test_replacement(&path)?;
+            let reader = self.run_once(&path, temporary, &mut launch, seed.clone(), replaced)?;
+            if let Some(message) = &reopened {
+                self.strip_empty_message(&path, message)?;
+                reader.reload();
+            }
+
+            if let Some(sentinel) = &self.abort_on {
+                let text = String::from_utf8_lossy(&reader.read()?).into_owned();
+                if text.lines().any(|line| line == sentinel) {
+                    return Err(ScrawlError::Aborted);
+                }
+            }
+
+            let message = match &self.on_empty {
+                OnEmpty::Keep => return Ok(reader),
+                _ if !reader.read()?.trim_ascii().is_empty() => return Ok(reader),
+                OnEmpty::Skip | OnEmpty::Stop | OnEmpty::Error => {
+                    return Err(ScrawlError::EmptyBuffer)
+                }
+                _ if replaced => return Err(ScrawlError::EmptyBuffer),
+                OnEmpty::Reopen { message } => message.clone(),
+            };
+            self.write_empty_message(&reader, &message)?;
+            /* The buffer is needed for the next round */
+            let mut reader = reader;
+            reader.temporary = false;
+            drop(reader);
+            reopened = Some(message);
+        }
+    }
+
+    /// Runs the editor on `path` once (unless the test replacement was `replaced` in), making a Reader for the result.
+    fn run_once<F>(
+        &mut self,
+        path: &Path,
+        temporary: bool,
+        launch: &mut F,
+        seed: Option<Vec<u8>>,
+        replaced: bool,
+    ) -> Result<Reader, ScrawlError>
+    where
+        F: FnMut(&mut Self, &Path) -> Result<Launched, ScrawlError>,
+    {
+        let launched = if replaced {
+            Launched {
+                editor: TEST_REPLACEMENT_VAR.into(),
+                args: vec![],
+                status: None,
+                stderr: String::new(),
+                relaunch: None,
+            }
+        } else {
+            let launched_at = modified_time(path);
+            let launched = launch(self, path)?;
+            if let Some(quiet_period) = self.wait_on_close {
+                let limit = self.wait_on_close_limit;
+                wait_for_quiet(path, launched_at, seed.as_deref(), quiet_period, limit);
+            }
+            launched
+        };
+
+        /* An editor that failed without touching the buffer most likely didn't like its command line */
+        let failed = launched.status.filter(|status| !status.success());
+        let failure = match failed {
+            Some(status) if !launched.stderr.is_empty() => Some(format!(
+                "{}: exited with {status}: {}",
+                launched.editor.to_string_lossy(),
+                launched.stderr.trim_end()
+            )),
+            _ => None,
+        };
+        let reader = self.reader(path.into(), temporary, launched, seed);
+        match failure {
+            Some(message) if !reader.was_modified().unwrap_or(true) => {
+                return Err(ScrawlError::FailedToOpenEditor(message))
+            }
+            _ => (),
+        }
+
+        if let Some(program) = &self.post_process {
+            self.run_post_process(program, &reader.path)?;
+            reader.reload();
+        }
+        Ok(reader)
+    }
+
+    /// Fills the buffer with `message` for `OnEmpty::Reopen`, inside the `wrap` markers if there are any.
+    fn write_empty_message(&self, reader: &Reader, message: &str) -> Result<(), ScrawlError> {
+        let message = with_newline(message);
+        let text = match (&self.wrap, &reader.region) {
+            (Some((header, footer)), Some((start, end))) => {
+                format!("{}{start}\n{message}{end}\n{footer}", with_newline(header))
+            }
+            _ => message,
+        };
+        let bytes = match &reader.encoding {
+            Some(label) => encode(text.into_bytes(), label)?,
+            None => text.into_bytes(),
+        };
+        fs::write(&reader.path, bytes)?;
+        Ok(())
+    }
+
+    /// Takes the lines of `message` back out of the buffer at `path`, leaving only what the user wrote.
+    fn strip_empty_message(&self, path: &Path, message: &str) -> Result<(), ScrawlError> {
+        let bytes = fs::read(path)?;
+        let bytes = match self.buffer_encoding() {
+            Some(label) => decode(bytes, label),
+            None => bytes,
+        };
+        let Ok(text) = String::from_utf8(bytes) else {
+            return Ok(());
+        };
+        let message: Vec<&str> = message
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        let kept: String = text
+            .split_inclusive('\n')
+            .filter(|line| !message.contains(&line.trim_end_matches(['\r', '\n'])))
+            .collect();
+        if kept.len() != text.len() {
+            let kept = match self.buffer_encoding() {
+                Some(label) => encode(kept.into_bytes(), label)?,
+                None => kept.into_bytes(),
+            };
+            fs::write(path, kept)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the `post_process` command over the buffer at `path`.
+    fn run_post_process(&self, program: &Program, path: &Path) -> Result<(), ScrawlError> {
+        let name = program.name.to_string_lossy();
+        let mut command = Command::new(&program.name);
+        command.args(&program.args);
+        match (&self.current_dir, env::current_dir()) {
+            (Some(dir), Ok(cwd)) => command.current_dir(dir).arg(cwd.join(path)),
+            _ => command.arg(path),
+        };
+        let output = command
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| ScrawlError::PostProcessFailed(format!("{name}: {e}")))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(ScrawlError::PostProcessFailed(format!(
+            "{name}: exited with {}: {}",
+            output.status,
+            stderr.trim_end()
+        )))
+    }
+
+    /// Starts `program` on `path`, without waiting for it.
+    fn start_program(
+        &mut self,
+        program: Program,
+        args: Vec<OsString>,
+        path: &Path,
+    ) -> Result<Started, ScrawlError> {
+        /* Keep what the editor says on stderr for the error message, unless the caller set stderr, which building the command uses up */
+        let capture = self.stderr.is_none();
+        let mut command = self.command(&program, &args, path);
+        let relaunch = Relaunch::of(&command, capture, self.command_hook.clone());
+        if capture {
+            command.stderr(Stdio::piped());
+        }
+        self.apply_command_hook(&mut command);
+        let mut child = command.spawn().map_err(|e| open_error(&program.name, e))?;
+        info!(
+            "opened {} on {}",
+            program.name.to_string_lossy(),
+            path.display()
+        );
+        let stderr = child.stderr.take().filter(|_| capture).map(tee_stderr);
+        Ok(Started {
+            child,
+            program,
+            args,
+            stderr,
+            relaunch,
+        })
+    }
+
+    /// Hands the running editor to `on_spawn`, then waits for it to exit.
+    fn wait_for<F: FnOnce(&Child)>(
+        &self,
+        mut started: Started,
+        on_spawn: F,
+    ) -> Result<Launched, ScrawlError> {
+        on_spawn(&started.child);
+        let status = started.child.wait()?;
+        let mut launched = self.launched(started.program, started.args, Some(status));
+        launched.relaunch = Some(started.relaunch);
+        if let Some(tee) = started.stderr {
+            launched.stderr = String::from_utf8_lossy(&tee.finish()).into_owned();
+        }
+        Ok(launched)
+    }
+
+    /// Has `start` open the editor on `path` without waiting for it, handing back an EditorHandle.
+    fn handle<F>(&mut self, path: PathBuf, start: F) -> Result<EditorHandle, ScrawlError>
+    where
+        F: FnOnce(&mut Self, &Path) -> Result<Started, ScrawlError>,
+    {
+        let seed = fs::read(&path).ok();
+
+        let (child, launched) = if apply_test_replacement(&path)? {
+            let launched = Launched {
+                editor: TEST_REPLACEMENT_VAR.into(),
+                args: vec![],
+                status: None,
+                stderr: String::new(),
+                relaunch: None,
+            };
+            (None, launched)
+        } else {
+            let started = start(self, &path)?;
+            let mut launched = self.launched(started.program, started.args, None);
+            launched.relaunch = Some(started.relaunch);
+            (Some(started.child), launched)
+        };
+
+        Ok(EditorHandle {
+            child,
+            reader: Some(self.reader(path, true, launched, seed)),
+        })
+    }
+
+    /// Wraps up a buffer the editor has been run on in a Reader.
+    fn reader(
+        &self,
+        path: PathBuf,
+        temporary: bool,
+        launched: Launched,
+        seed: Option<Vec<u8>>,
+    ) -> Reader {
+        let shared = self.reused.clone().filter(|b| temporary && b.path == path);
+        Reader {
+            extension: path
+                .extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default(),
+            path,
+            temporary,
+            captured: None,
+            editor: launched.editor,
+            args: launched.args,
+            status: launched.status,
+            newline: self.newline_style(),
+            region: if temporary { self.region() } else { None },
+            encoding: self.buffer_encoding().map(String::from),
+            max_size: self.max_size,
+            on_read: self.on_read.clone(),
+            shared,
+            seed,
+            cache: Mutex::new(None),
+            stream: None,
+            relaunch: launched.relaunch,
+        }
+    }
+
+    /// Runs `program` as a filter, seeding its stdin and capturing its stdout in memory.
+    fn pipe<F: FnOnce(&Child)>(
+        &mut self,
+        contents: Contents,
+        program: Program,
+        args: Vec<OsString>,
+        on_spawn: F,
+    ) -> Result<Reader, ScrawlError> {
+        contents.check_sources()?;
+        let extension = self.buffer_extension(&contents);
+        let mut seed = contents.into_bytes()?;
+        if let Some(label) = self.buffer_encoding() {
+            seed = encode(seed, label)?;
+        }
+
+        let (captured, launched) = match env::var_os(TEST_REPLACEMENT_VAR) {
+            Some(replacement) => (
+                replacement.as_encoded_bytes().to_vec(),
+                Launched {
+                    editor: TEST_REPLACEMENT_VAR.into(),
+                    args: vec![],
+                    status: None,
+                    stderr: String::new(),
+                    relaunch: None,
+                },
+            ),
+            None => {
+                /* The filter only works through its pipes, so they take precedence over the hook */
+                let mut command = self.command_without_path(&program, &args);
+                self.apply_command_hook(&mut command);
+                command.stdin(Stdio::piped()).stdout(Stdio::piped());
+                let mut child = command.spawn().map_err(|e| open_error(&program.name, e))?;
+                info!(
+                    "piping the buffer through {}",
+                    program.name.to_string_lossy()
+                );
+                on_spawn(&child);
+
+                /* Feed stdin from another thread so a chatty filter can't deadlock on a full stdout */
+                let mut stdin = child.stdin.take().expect("stdin is piped");
+                let input = seed.clone();
+                let writer = thread::spawn(move || stdin.write_all(&input));
+                let output = child.wait_with_output()?;
+                match writer.join().expect("stdin writer panicked") {
+                    /* The filter is allowed to stop reading early */
+                    Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e.into()),
+                    _ => (),
+                }
+                let launched = self.launched(program, args, Some(output.status));
+                (output.stdout, launched)
+            }
+        };
+
+        Ok(Reader {
+            path: PathBuf::new(),
+            temporary: false,
+            captured: Some(captured),
+            editor: launched.editor,
+            args: launched.args,
+            status: launched.status,
+            extension,
+            newline: self.newline_style(),
+            region: None,
+            encoding: self.buffer_encoding().map(String::from),
+            max_size: self.max_size,
+            on_read: self.on_read.clone(),
+            shared: None,
+            seed: Some(seed),
+            cache: Mutex::new(None),
+            stream: None,
+            relaunch: None,
+        })
+    }
+
+    /// Makes sure the file about to be edited exists, if asked to.
+    fn create_edit_file(&self, path: &Path) -> Result<(), ScrawlError> {
+        if !self.create || path.exists() {
+            return Ok(());
+        }
+
+        let create = || -> io::Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(path)
+                .map(|_| ())
+        };
+        create().map_err(|_| ScrawlError::FailedToCreateFile(path.into()))
+    }
+
+    /// Returns the flags Scrawl adds for the options set on the Editor, based on which editor `program` is.
+    fn injected_flags(&self, program: &OsStr) -> Vec<OsString> {
+        let mut flags = Vec::new();
+        if let Some(kind) = self.injected_kind(program) {
+            if self.read_only {
+                flags.extend(kind.read_only_flags().iter().map(OsString::from));
+            }
+            if self.new_window {
+                flags.extend(kind.new_window_flags().iter().map(OsString::from));
+            }
+            if self.quiet {
+                flags.extend(kind.quiet_flags().iter().map(OsString::from));
+            }
+            if !self.companions.is_empty() && self.implicit_flags() {
+                flags.extend(kind.layout_flags(self.layout).iter().map(OsString::from));
+            }
+            match (self.selection, self.cursor) {
+                (Some((start, end)), _) => match kind.select_flags(start, end) {
+                    Some(select) => flags.extend(select),
+                    None => flags.extend(kind.goto_flags(start)),
+                },
+                (None, Some(at)) => flags.extend(kind.goto_flags(at)),
+                (None, None) => (),
+            }
+        }
+        flags
+    }
+
+    /// Fails if `require_interactive` is set and there is no terminal for the editor to use.
+    fn check_interactive(&self) -> Result<(), ScrawlError> {
+        let interactive = io::stdin().is_terminal() && io::stdout().is_terminal();
+        if self.require_interactive && !interactive && env::var_os(TEST_REPLACEMENT_VAR).is_none() {
+            return Err(ScrawlError::NotInteractive);
+        }
+        Ok(())
+    }
+
+    /* Where the cursor starts: at the selection, if there is one */
+    fn start_position(&self) -> Option<Position> {
+        self.selection.map(|(start, _)| start).or(self.cursor)
+    }
+
+    /* The kind of editor `program` is, if flags should be injected for it */
+    fn injected_kind(&self, program: &OsStr) -> Option<EditorKind> {
+        EditorKind::detect(program).filter(|_| self.flag_injection != FlagInjection::Never)
+    }
+
+    /* Whether flags the caller didn't ask for may be added too */
+    fn implicit_flags(&self) -> bool {
+        match self.flag_injection {
+            FlagInjection::Auto => env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal(),
+            FlagInjection::Always => true,
+            FlagInjection::Never => false,
+        }
+    }
+
+    /// Records what `command` ran, with the arguments in the order they were passed.
+    fn launched(
+        &self,
+        program: Program,
+        args: Vec<OsString>,
+        status: Option<ExitStatus>,
+    ) -> Launched {
+        let mut used = program.args;
+        used.extend(self.injected_flags(&program.name));
+        used.extend(args);
+        Launched {
+            editor: program.name,
+            args: used,
+            status,
+            stderr: String::new(),
+            relaunch: None,
+        }
+    }
+
+    /// Builds the command that opens `program` on `path`, applying any configured stdio.
+    fn command(&mut self, program: &Program, args: &[OsString], path: &Path) -> Command {
+        let mut command = self.command_without_path(program, &[]);
+
+        /* The editor would otherwise resolve a relative path against its own directory */
+        let mut path = PathBuf::from(path);
+        if let (Some(_), true, Ok(cwd)) =
+            (&self.current_dir, path.is_relative(), env::current_dir())
+        {
+            path = cwd.join(path);
+        }
+        let mut path_arg = path.clone();
+        if let (Some(kind), Some(at)) = (self.injected_kind(&program.name), self.start_position()) {
+            path_arg = kind.goto_path(&path, at);
+        }
+
+        /* The path goes first, unless an argument says where it goes */
+        let filled: Vec<Option<OsString>> = args
+            .iter()
+            .map(|a| fill_file_arg(a, &path, &path_arg))
+            .collect();
+        if !args.iter().any(|a| places_path(a)) {
+            command.arg(&path_arg);
+        }
+        for (arg, filled) in args.iter().zip(filled) {
+            command.arg(filled.as_deref().unwrap_or(arg));
+        }
+        command.args(&self.companions);
+        command
+    }
+
+    /// Runs `open` with a read-only buffer seeded with `reference` opened alongside, deleting it afterwards.
+    fn with_reference<F>(&mut self, reference: Contents, open: F) -> Result<Reader, ScrawlError>
+    where
+        F: FnOnce(&mut Self) -> Result<Reader, ScrawlError>,
+    {
+        /* The reference mustn't take over the buffer kept for `reuse_buffer` */
+        let kept = self.reused.take();
+        let reuse = std::mem::replace(&mut self.reuse_buffer, false);
+        let created = self.create_buffer_file(reference);
+        let memory = std::mem::replace(&mut self.reused, kept);
+        self.reuse_buffer = reuse;
+        let path = created?;
+
+        /* Deleted, or freed if memory-backed, once this goes */
+        let _reference = memory.unwrap_or_else(|| {
+            Arc::new(SharedBuffer {
+                path: path.clone(),
+                memory: None,
+            })
+        });
+        make_read_only(&path)?;
+
+        self.companions = vec![path];
+        self.layout = Layout::Split;
+        let reader = open(self);
+        self.companions.clear();
+        reader
+    }
+
+    /// Runs `edit` on the first of `paths` with the rest opened alongside in tabs, returning a Reader for each.
+    fn edit_together<F>(&mut self, paths: Vec<PathBuf>, edit: F) -> Result<Vec<Reader>, ScrawlError>
+    where
+        F: FnOnce(&mut Self, &Path) -> Result<Reader, ScrawlError>,
+    {
+        let Some((first, rest)) = paths.split_first() else {
+            return Ok(vec![]);
+        };
+        for path in rest {
+            self.create_edit_file(path)?;
+        }
+        let seeds: Vec<Option<Vec<u8>>> = rest.iter().map(|path| fs::read(path).ok()).collect();
+
+        self.companions = rest.to_vec();
+        self.layout = Layout::Tabs;
+        let first = edit(self, first);
+        self.companions.clear();
+        let first = first?;
+
+        /* The other files were edited by the same editor, so their Readers say so too */
+        let mut readers = Vec::with_capacity(paths.len());
+        for (path, seed) in rest.iter().zip(seeds) {
+            apply_test_replacement(path)?;
+            let launched = Launched {
+                editor: first.editor.clone(),
+                args: first.args.clone(),
+                status: first.status,
+                stderr: String::new(),
+                relaunch: None,
+            };
+            readers.push(self.reader(path.clone(), false, launched, seed));
+        }
+        readers.insert(0, first);
+        Ok(readers)
+    }
+
+    /// Lets the caller's `command_hook` adjust a command that is about to be spawned.
+    fn apply_command_hook(&mut self, command: &mut Command) {
+        if let Some(hook) = &self.command_hook {
+            hook.apply(command);
+        }
+    }
+
+    /// Builds the command that runs `program` with `args`, applying any configured directory and stdio.
+    fn command_without_path(&mut self, program: &Program, args: &[OsString]) -> Command {
+        let mut command = Command::new(&program.name);
+        command
+            .args(&program.args)
+            .args(self.injected_flags(&program.name))
+            .args(args);
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        if let Some(stdin) = self.stdin.take() {
+            command.stdin(stdin);
+        }
+        if let Some(stdout) = self.stdout.take() {
+            command.stdout(stdout);
+        }
+        if let Some(stderr) = self.stderr.take() {
+            command.stderr(stderr);
+        }
+        command
+    }
+
+    /// Creates a temporary file to use a buffer for the user's editor.
+    fn create_buffer_file(&mut self, contents: Contents) -> Result<PathBuf, ScrawlError> {
+        contents.check_sources()?;
+
+        /* Generate unique path to a temporary file */
+        let extension = self.buffer_extension(&contents);
+        let temp_file = if self.debug_filenames {
+            readable_file_name(&extension)
+        } else {
+            unique_file_name(&extension)
+        };
+
+        /* Create it in a Scrawl directory in the first of the temporary directories we can write to, or in the project */
+        let dirs: Vec<PathBuf> = match &self.project {
+            Some(root) => vec![root.join(PROJECT_DIR)],
+            None => temp_dir_candidates()
+                .into_iter()
+                .map(|base| base.join(&self.temp_subdir))
+                .collect(),
+        };
+        let mut tried = Vec::new();
+        let mut created = self.reused_buffer_file(&temp_file);
+        let mut memory = None;
+        if created.is_none() && self.memory_backed {
+            if let Some((path, file)) = memory_file(&temp_file) {
+                /* The path names this handle, so it is kept and a duplicate is used for seeding */
+                created = Some((path, file.try_clone()?));
+                memory = Some(file);
+            }
+        }
+        for temp_dir in dirs.into_iter().filter(|_| created.is_none()) {
+            if let Ok(file) = create_temp_file(&temp_dir, &temp_file, self.create_temp_parents) {
+                let path = temp_dir.join(&temp_file);
+                if self.memory_backed {
+                    restrict_permissions(&path)?;
+                }
+                created = Some((path, file));
+                break;
+            }
+            tried.push(temp_dir);
+        }
+        let Some((temp_file_path, mut temp_file)) = created else {
+            return Err(ScrawlError::FailedToCreateTempfile(tried));
+        };
+        if memory.is_some() {
+            /* Held by the Editor and its Readers like a reused buffer, so whoever is last frees it */
+            self.reused = Some(Arc::new(SharedBuffer {
+                path: temp_file_path.clone(),
+                memory,
+            }));
+        } else if self.reuse_buffer
+            && self
+                .reused
+                .as_ref()
+                .is_none_or(|b| b.path != temp_file_path)
+        {
+            self.reused = Some(Arc::new(SharedBuffer {
+                path: temp_file_path.clone(),
+                memory: None,
+            }));
+        }
+
+        /* Seed the contents of this temporary file */
+        match (&self.wrap, self.region()) {
+            (Some((header, footer)), Some((start, end))) => {
+                /* The contents are streamed in between, so they never have to be held in memory */
+                writeln!(temp_file, "{}{start}", with_newline(header))?;
+                let seed_start = temp_file.stream_position()?;
+                contents.write_to(&mut temp_file)?;
+                let seed_end = temp_file.stream_position()?;
+                if seed_end > seed_start && !ends_with_newline(&temp_file_path, seed_end)? {
+                    writeln!(temp_file)?;
+                }
+                write!(temp_file, "{end}\n{footer}")?;
+            }
+            _ => contents.write_to(&mut temp_file)?,
+        }
+        drop(temp_file);
+
+        /* Find where the cursor should start */
+        self.cursor = match self.strip_cursor_marker(&temp_file_path)? {
+            Some(at) => Some(at),
+            None => self.end_cursor(&temp_file_path),
+        };
+
+        /* Transcode the seeded buffer into the encoding it is edited in */
+        if let Some(label) = self.buffer_encoding() {
+            let seeded = fs::read(&temp_file_path)?;
+            fs::write(&temp_file_path, encode(seeded, label)?)?;
+        }
+
+        /* Make sure the editor sees the seeded contents, even on network filesystems */
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&temp_file_path)?
+            .sync_all()?;
+        sync_dir(temp_file_path.parent().unwrap_or(&temp_file_path));
+
+        /* Return the path */
+        Ok(temp_file_path)
+    }
+
+    /// Truncates the buffer kept by `reuse_buffer`, if there is one with the same extension as `name`.
+    fn reused_buffer_file(&self, name: &str) -> Option<(PathBuf, fs::File)> {
+        let reused = self.reused.as_ref().filter(|_| self.reuse_buffer)?;
+        let wanted = Path::new(name).extension();
+        if reused.path.extension() != wanted {
+            return None;
+        }
+        let file = fs::File::create(&reused.path).ok()?;
+        Some((reused.path.clone(), file))
+    }
+
+    /// The encoding set with `encoding`, unless the buffer is binary.
+    fn buffer_encoding(&self) -> Option<&str> {
+        self.encoding.as_deref().filter(|_| !self.binary)
+    }
+
+    /// Removes the first cursor marker from the buffer, returning where it was.
+    fn strip_cursor_marker(&self, path: &Path) -> Result<Option<Position>, ScrawlError> {
+        /* Binary buffers are handed over untouched */
+        if self.binary {
+            return Ok(None);
+        }
+        let Some(marker) = self.cursor_marker.as_deref().filter(|m| !m.is_empty()) else {
+            return Ok(None);
+        };
+        /* A buffer that isn't UTF-8 can't contain the marker */
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Ok(None);
+        };
+        let Some(offset) = contents.find(marker) else {
+            return Ok(None);
+        };
+
+        let before = &contents[..offset];
+        let at = Position {
+            line: before.matches('\n').count() + 1,
+            column: before
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .count()
+                + 1,
+        };
+        fs::write(path, [before, &contents[offset + marker.len()..]].concat())?;
+        Ok(Some(at))
+    }
+
+    /// The sentinels that mark out the contents of a wrapped buffer, if it is wrapped.
+    fn region(&self) -> Option<(String, String)> {
+        match (&self.wrap, self.binary) {
+            (Some(_), false) => Some(self.sentinels.clone()),
+            _ => None,
+        }
+    }
+
+    /// The line endings Readers write out with; binary buffers are always left as they are.
+    fn newline_style(&self) -> NewlineStyle {
+        if self.binary {
+            NewlineStyle::Preserve
+        } else {
+            self.newline
+        }
+    }
+
+    /// The extension used when neither `ext()` nor the seed file provide one.
+    fn default_extension(&self) -> &'static str {
+        if self.binary {
+            BINARY_EXT
+        } else {
+            DEFAULT_EXT
+        }
+    }
+
+    /// Where the buffer ends, if the cursor should start there.
+    fn end_cursor(&self, path: &Path) -> Option<Position> {
+        if !self.cursor_at_end || self.binary {
+            return None;
+        }
+        let contents = fs::read_to_string(path).ok()?;
+        /* A trailing newline ends the last line rather than starting an empty one, which editors won't put the cursor on */
+        let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+        let contents = contents.strip_suffix('\r').unwrap_or(contents);
+        let last_line = contents.rsplit('\n').next().unwrap_or_default();
+        Some(Position {
+            line: contents.matches('\n').count() + 1,
+            column: last_line.chars().count() + 1,
+        })
+    }
+
+    /// Returns the extension for the buffer file: the one set with `ext()`, else the seed file's own, else the default.
+    fn buffer_extension(&self, contents: &Contents) -> String {
+        if let Some(ext) = &self.extension {
+            return ext.clone();
+        }
+        let Some(source) = contents.source_path() else {
+            return self.default_extension().into();
+        };
+        /* An extension that isn't UTF-8 can't be matched to a format anyway, and mustn't be mangled into the buffer's name */
+        source
+            .extension()
+            .and_then(OsStr::to_str)
+            .map_or_else(|| self.default_extension().into(), |ext| format!(".{ext}"))
+    }
+
+    /// Anchors a relative editor path like `./scripts/edit.sh` to the directory the Editor was made in, so `current_dir` and later `chdir`s don't change which file it names.
+    fn anchored(&self, mut program: Program) -> Program {
+        let path = Path::new(&program.name);
+        if path.is_relative() && path.components().count() > 1 {
+            if let Some(dir) = &self.original_dir {
+                let relative = path
+                    .components()
+                    .filter(|c| *c != std::path::Component::CurDir);
+                program.name = dir.join(relative.collect::<PathBuf>()).into_os_string();
+            }
+        }
+        program
+    }
+
+    /// Returns the name of the editor to use if user specified, or a list of editors to try if Default is selected.
+    fn get_editor_programs(&self) -> Vec<Program> {
+        let mut programs = Vec::with_capacity(3);
+        /* Editors chosen for this OS come before anything the user has set */
+        programs.extend(
+            self.os_editors
+                .iter()
+                .filter(|(os, _)| os.is_current())
+                .map(|(_, editor)| Program::from(editor)),
+        );
+        /* Check the ENV variables for programs, SCRAWL_EDITOR, VISUAL then EDITOR unless configured */
+        programs.extend(
+            self.env_vars
+                .iter()
+                .filter(|_| !self.ignore_env)
+                .filter_map(|var| {
+                    let value = env::var_os(var);
+                    debug!("${} is {value:?}", var.to_string_lossy());
+                    value
+                })
+                .filter_map(|value| Program::parse(&value)),
+        );
+
+        /* Add some common programs */
+        if cfg!(windows) {
+            programs.push(Program::from("notepad.exe"));
+        } else {
+            /* Debian's alternatives for the system editor come first */
+            let p: Vec<&str> = vec![
+                "sensible-editor",
+                "editor",
+                "vim",
+                "neovim",
+                "nvim",
+                "nano",
+                "emacs",
+                "mcedit",
+                "tilde",
+                "micro",
+                "helix",
+                "ne",
+                "vi",
+            ];
+            programs.extend(p.into_iter().map(Program::from));
+        }
+
+        /* e.g. VISUAL=vim and EDITOR=vim, which are also built in; only the first try is worth making */
+        let mut unique: Vec<Program> = Vec::with_capacity(programs.len());
+        for program in programs.into_iter().map(|p| self.anchored(p)) {
+            if !program.name.is_empty() && !unique.contains(&program) {
+                unique.push(program);
+            }
+        }
+        unique
+    }
+}
+
+/* The default EditorState, in builder mode */
+#[derive(Debug, Clone, Copy)]
+/// Holds the data and implementation for the initial state of the Editor struct.
+pub struct DefaultState {}
+impl EditorState for DefaultState {}
+
+impl Editor<DefaultState> {
+    /// Checks there is an editor to open, and a user to open it for, before a buffer is made. Under `SCRAWL_TEST_REPLACEMENT` no editor is needed.
+    fn preflight(&self) -> Result<(), ScrawlError> {
+        self.check_interactive()?;
+        if env::var_os(TEST_REPLACEMENT_VAR).is_none() {
+            self.resolve_editor()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the first of the user's editors which can be found, without launching anything.
+    fn resolve_editor(&self) -> Result<Program, ScrawlError> {
+        let programs = self.get_editor_programs();
+        for program in &programs {
+            if let Some(e) = not_executable(program) {
+                return Err(e);
+            }
+            if is_installed(&program.name) {
+                return Ok(program.clone());
+            }
+        }
+        Err(not_found(programs))
+    }
+
+    /// Specify which extension should be used on the temporary file (often used by text editors to infer syntax highlighting), either as a string (`"rs"` or `".rs"`) or an `Extension`.
+    pub fn ext<S: AsRef<str>>(&mut self, ext: S) -> &mut Self {
+        self.extension = Some(dotted(ext.as_ref()));
+        self
+    }
+
+    /// Set which environment variables name the user's editor, in the order they are checked. Defaults to `SCRAWL_EDITOR`, `VISUAL`, then `EDITOR`, e.g. `.env_vars(["MYAPP_EDITOR", "VISUAL", "EDITOR"])` lets an application override them. Variables that are empty are skipped, and each editor is only tried once.
+    pub fn env_vars<I, V>(&mut self, vars: I) -> &mut Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<OsString>,
+    {
+        self.env_vars = vars.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Don't look at any environment variables for the user's editor, so the choice only depends on `editor_for` and the built-in list, e.g. for tests that must not depend on who runs them. Defaults to false.
+    pub fn ignore_env(&mut self, ignore: bool) -> &mut Self {
+        self.ignore_env = ignore;
+        self
+    }
+
+    /// Call `f` when the editor named by the user's environment variables (see `env_vars`) isn't on `PATH` or fails to start, and another is used instead, with the name of each, e.g. to warn that "your $EDITOR `foo` wasn't found, using vim instead". This is most often a shell alias, which only exists in an interactive shell. It is not called when an `editor_for` editor is chosen ahead of a working one.
+    pub fn on_fallback<F: FnMut(&OsStr, &OsStr) + Send + 'static>(&mut self, f: F) -> &mut Self {
+        self.on_fallback = Some(FallbackHook(Box::new(f)));
+        self
+    }
+
+    /* Tells the `on_fallback` callback if `used` was started instead of the user's own editor, which wasn't found or was one of the `failed` */
+    fn report_fallback(&mut self, used: &OsStr, failed: &[OsString]) {
+        if self.on_fallback.is_none() && !cfg!(feature = "log") {
+            return;
+        }
+        let wanted = self
+            .env_vars
+            .iter()
+            .filter(|_| !self.ignore_env)
+            .filter_map(env::var_os)
+            .find_map(|value| Program::parse(&value))
+            .map(|wanted| self.anchored(wanted));
+        let Some(wanted) = wanted.filter(|wanted| wanted.name != used) else {
+            return;
+        };
+        if find_program(&wanted.name).is_none() || failed.contains(&wanted.name) {
+            warn!(
+                "{} could not be used, falling back to {}",
+                wanted.name.to_string_lossy(),
+                used.to_string_lossy()
+            );
+            if let Some(FallbackHook(f)) = &mut self.on_fallback {
+                f(&wanted.name, used);
+            }
+        }
+    }
+
+    /// Use `editor` when running on `os`, ahead of the user's environment variables, e.g. `.editor_for(Os::Windows, "notepad").editor_for(Os::MacOs, "nano")`. Other operating systems choose as usual. If several are registered for the same OS they are tried in order.
+    pub fn editor_for<E: AsRef<OsStr>>(&mut self, os: Os, editor: E) -> &mut Self {
+        self.os_editors.push((os, editor.as_ref().into()));
+        self
+    }
+
+    /* Returns a different struct, consumes the Editor instead of returning a
+       reference; enforces a certain builder grammar.
+    */
+    /// Specify which editor should be opened instead of the user's default.
+    pub fn editor<S: AsRef<OsStr>>(self, editor: S) -> Editor<SpecificEditorState> {
+        self.with_state(SpecificEditorState {
+            editor: OsString::from(editor.as_ref()),
+            args: None,
+        })
+    }
+
+    /// Specify the editor as a complete argv, e.g. `["code", "--wait", "--new-window"]`: the first element is the program and the rest are passed to it verbatim, without any shell-style splitting. This is the same as `editor()` with the first element followed by `arg()` for each of the others, so arguments added with `arg()` afterwards come after these, and the tokens described under `arg()` work in any of them.
+    pub fn editor_argv<I, S>(self, argv: I) -> Editor<SpecificEditorState>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        let mut argv = argv.into_iter().map(Into::into);
+        let editor = argv.next().unwrap_or_default();
+        self.with_state(SpecificEditorState {
+            editor,
+            args: Some(argv.collect()),
+        })
+    }
+
+    /// Instead of opening an editor, pass the buffer's contents through `transform` and save what it returns, e.g. to skip the editor for a `--yes` flag or in tests. The result is read back through a Reader exactly as if an editor had saved it.
+    pub fn transform<F: FnOnce(String) -> String>(self, transform: F) -> Editor<TransformState<F>> {
+        self.with_state(TransformState {
+            transform: Some(transform),
+        })
+    }
+
+    /* Moves the configuration over to a new state */
+    fn with_state<T: EditorState>(self, state: T) -> Editor<T> {
+        Editor {
+            extension: self.extension,
+            temp_subdir: self.temp_subdir,
+            project: self.project,
+            create_temp_parents: self.create_temp_parents,
+            debug_filenames: self.debug_filenames,
+            env_vars: self.env_vars,
+            ignore_env: self.ignore_env,
+            os_editors: self.os_editors,
+            create: self.create,
+            read_only: self.read_only,
+            new_window: self.new_window,
+            quiet: self.quiet,
+            require_interactive: self.require_interactive,
+            pipe_mode: self.pipe_mode,
+            binary: self.binary,
+            newline: self.newline,
+            encoding: self.encoding,
+            wrap: self.wrap,
+            sentinels: self.sentinels,
+            flag_injection: self.flag_injection,
+            max_size: self.max_size,
+            post_process: self.post_process,
+            command_hook: self.command_hook,
+            on_read: self.on_read,
+            on_fallback: self.on_fallback,
+            on_empty: self.on_empty,
+            abort_on: self.abort_on,
+            reuse_buffer: self.reuse_buffer,
+            memory_backed: self.memory_backed,
+            reused: self.reused,
+            wait_on_close: self.wait_on_close,
+            wait_on_close_limit: self.wait_on_close_limit,
+            cursor_marker: self.cursor_marker,
+            cursor_at_end: self.cursor_at_end,
+            selection: self.selection,
+            cursor: self.cursor,
+            companions: self.companions,
+            layout: self.layout,
+            current_dir: self.current_dir,
+            original_dir: self.original_dir,
+            stdin: self.stdin,
+            stdout: self.stdout,
+            stderr: self.stderr,
+            state,
+        }
+    }
+
+    /* Start the first installed candidate that starts, moving on from any that are broken */
+    fn start(&mut self, path: &Path) -> Result<Started, ScrawlError> {
+        let programs = self.get_editor_programs();
+        let mut failure = None;
+        let mut failed = Vec::new();
+        for program in &programs {
+            if let Some(e) = not_executable(program) {
+                return Err(e);
+            }
+            if !is_installed(&program.name) {
+                continue;
+            }
+            match self.start_program(program.clone(), vec![], path) {
+                Ok(started) => {
+                    self.report_fallback(&program.name, &failed);
+                    return Ok(started);
+                }
+                Err(e) => {
+                    failed.push(program.name.clone());
+                    failure = Some(e);
+                }
+            }
+        }
+        Err(failure.unwrap_or_else(|| not_found(programs)))
+    }
+
+    /* Launch the editor and wait for it to exit */
+    fn launch<F: FnOnce(&Child)>(
+        &mut self,
+        path: &Path,
+        on_spawn: F,
+    ) -> Result<Launched, ScrawlError> {
+        let started = self.start(path)?;
+        self.wait_for(started, on_spawn)
+    }
+
+    /// Opens the user's editor. If a candidate is installed but fails to start, the next one is tried.
+    pub fn open(&mut self, contents: Contents) -> Result<Reader, ScrawlError> {
+        self.open_with(contents, |_| ())
+    }
+
+    /// Opens the user's editor like `open`, calling `on_spawn` with the running editor process before waiting for it to exit, e.g. to update a status line or start a watcher. `on_spawn` is not called if no process is started, such as under `SCRAWL_TEST_REPLACEMENT`.
+    pub fn open_with<F: FnOnce(&Child)>(
+        &mut self,
+        contents: Contents,
+        on_spawn: F,
+    ) -> Result<Reader, ScrawlError> {
+        if self.pipe_mode {
+            /* Under the test replacement no editor is needed, so none is looked for */
+            let program = if env::var_os(TEST_REPLACEMENT_VAR).is_some() {
+                Program::from(TEST_REPLACEMENT_VAR)
+            } else {
+                let program = self.resolve_editor()?;
+                self.report_fallback(&program.name, &[]);
+                program
+            };
+            return self.pipe(contents, program, vec![], on_spawn);
+        }
+
+        /* Create a temporary file to use as a buffer */
+        self.preflight()?;
+        let path = self.create_buffer_file(contents)?;
+        /* Only the first editor is handed over if `on_empty` reopens it */
+        let mut on_spawn = Some(on_spawn);
+        self.run(path, true, |editor, path| {
+            editor.launch(path, |child| on_spawn.take().map_or((), |f| f(child)))
+        })
+    }
+
+    /// Opens the editor without waiting for it to exit, returning an `EditorHandle` to poll, wait on or kill it with, e.g. from an event loop. The buffer is always a file; `pipe_mode` and `wait_on_close` are ignored.
+    pub fn spawn(&mut self, contents: Contents) -> Result<EditorHandle, ScrawlError> {
+        self.preflight()?;
+        let path = self.create_buffer_file(contents)?;
+        self.handle(path, Self::start)
+    }
+
+    /// Opens the buffer in the application the OS associates with its extension instead of a text editor, waiting on the buffer as with `wait_on_close` where the opener returns at once.
+    pub fn open_with_os_default(&mut self, contents: Contents) -> Result<Reader, ScrawlError> {
+        self.check_interactive()?;
+        let path = self.create_buffer_file(contents)?;
+        let (opener, waits) = os_opener();
+        let wait_on_close = self.wait_on_close;
+        if !waits {
+            self.wait_on_close = wait_on_close.or(Some(OPENER_QUIET_PERIOD));
+        }
+        let reader = self.run(path, true, |editor, path| {
+            let started = editor.start_program(opener.clone(), vec![], path)?;
+            editor.wait_for(started, |_| ())
+        });
+        self.wait_on_close = wait_on_close;
+        reader
+    }
+
+    /// Like `open`, but returns an `EditSession` bundling the Reader with what happened while editing.
+    pub fn open_session(&mut self, contents: Contents) -> Result<EditSession, ScrawlError> {
+        EditSession::new(self.open(contents)?)
+    }
+
+    /// Like `edit`, but returns an `EditSession` bundling the Reader with what happened while editing.
+    pub fn edit_session<P: AsRef<Path>>(&mut self, path: P) -> Result<EditSession, ScrawlError> {
+        EditSession::new(self.edit(path)?)
+    }
+
+    /// Opens the editor on a writable buffer seeded with `scratch` alongside a read-only one seeded with `reference`, e.g. for writing review comments next to the text under review, and returns the Reader for `scratch` only. The reference is made read-only on disk rather than with `read_only`'s flags, which would apply to both buffers, so editors warn about or refuse saving it; Vim is also passed `-o` to show the two in split windows. The reference buffer is deleted once the editor exits. Not supported with `pipe_mode`, which has no files to open.
+    pub fn open_with_reference(
+        &mut self,
+        reference: Contents,
+        scratch: Contents,
+    ) -> Result<Reader, ScrawlError> {
+        self.with_reference(reference, |editor| editor.open(scratch))
+    }
+
+    /// Opens one editor on all of `paths`, in tabs where the editor can be told to (`-p` for Vim), and returns a Reader for each, in the same order. The files are edited in place like with `edit`, never deleted, and created first if `create` is set. Options applied after the editor closes, such as `post_process` and `on_empty`, only apply to the first file, and only its Reader can `reedit`.
+    pub fn edit_files<I: IntoIterator<Item = PathBuf>>(
+        &mut self,
+        paths: I,
+    ) -> Result<Vec<Reader>, ScrawlError> {
+        let paths = paths.into_iter().collect();
+        self.edit_together(paths, |editor, path| editor.edit(path))
+    }
+
+    /// Opens the editor once for each item, in order, returning the edited values. `on_empty` decides what happens when an item is saved empty.
+    pub fn edit_each<I: IntoIterator<Item = String>>(
+        &mut self,
+        items: I,
+        on_empty: OnEmpty,
+    ) -> Result<Vec<String>, ScrawlError> {
+        edit_each_with(self, items, on_empty, Self::open)
+    }
+
+    /// Opens a file for editing in the User's editor. Fails with `ScrawlError::EditorNotFound`, listing what was tried, if none of the candidates are installed.
+    pub fn edit<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader, ScrawlError> {
+        let path = path.as_ref();
+        self.preflight()?;
+        self.create_edit_file(path)?;
+        self.cursor = self.end_cursor(path);
+        self.run(path.into(), false, |editor, path| {
+            editor.launch(path, |_| ())
+        })
+    }
+}
+
+/// A variant of the Editor struct with a specific command and arguments for the text editor instead of the user's defaults. This struct is created when an editor is specified.
+#[derive(Debug)]
+pub struct SpecificEditorState {
+    args: Option<Vec<OsString>>,
+    editor: OsString,
+}
+impl EditorState for SpecificEditorState {}
+
+impl Editor<SpecificEditorState> {
+    /// Checks there is a user to open the editor for before a buffer is made.
+    fn preflight(&self) -> Result<(), ScrawlError> {
+        self.check_interactive()
+    }
+
+    /// Add arguments that you want to be used when the command is run. The first argument is the file being used as the buffer, unless an argument contains `{}` or `{path}`, which is replaced with the file's path instead (e.g. `.arg("--").arg("{}")`). Requires that a specific editor has been set.
+    ///
+    /// For wrapper scripts that need to know more about the buffer, these tokens are replaced too, without moving the path: `{name}` with its file name (`1674864208_123_17.rs`), `{ext}` with its extension without the dot (`rs`), and `{dir}` with the directory it is in. Anything else in braces is passed on as it is.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Self {
+        self.state
+            .args
+            .get_or_insert(vec![])
+            .push(OsString::from(arg.as_ref()));
+        self
+    }
+
+    /// Specify which extension should be used on the temporary file (often used by text editors to infer syntax highlighting), either as a string (`"rs"` or `".rs"`) or an `Extension`.
+    pub fn ext<S: AsRef<str>>(&mut self, ext: S) -> &mut Self {
+        self.extension = Some(dotted(ext.as_ref()));
+        self
+    }
+
+    /* Open the editor, store a handle to the child process */
+    fn start(&mut self, path: &Path) -> Result<Started, ScrawlError> {
+        let program = self.anchored(Program::from(self.state.editor.as_os_str()));
+        let args = self.state.args.clone().unwrap_or_default();
+        self.start_program(program, args, path)
+    }
+
+    /* Launch the editor and wait for it to exit */
+    fn launch<F: FnOnce(&Child)>(
+        &mut self,
+        path: &Path,
+        on_spawn: F,
+    ) -> Result<Launched, ScrawlError> {
+        let started = self.start(path)?;
+        self.wait_for(started, on_spawn)
+    }
+
+    /// Opens the specified editor. Fails with `ScrawlError::FailedToOpenEditor`, naming the editor and the OS error, if it can't be started.
+    ///
+    /// The editor's stderr is passed through, and a copy is kept for the error if the editor fails without changing the buffer, unless `stderr()` is set.
+    pub fn open(&mut self, contents: Contents) -> Result<Reader, ScrawlError> {
+        self.open_with(contents, |_| ())
+    }
+
+    /// Opens the editor like `open`, calling `on_spawn` with the running editor process before waiting for it to exit, e.g. to update a status line or start a watcher. `on_spawn` is not called if no process is started, such as under `SCRAWL_TEST_REPLACEMENT`.
+    pub fn open_with<F: FnOnce(&Child)>(
+        &mut self,
+        contents: Contents,
+        on_spawn: F,
+    ) -> Result<Reader, ScrawlError> {
+        if self.pipe_mode {
+            let program = self.anchored(Program::from(self.state.editor.as_os_str()));
+            let args = self.state.args.clone().unwrap_or_default();
+            return self.pipe(contents, program, args, on_spawn);
+        }
+
+        /* Create a temporary file to use as a buffer */
+        self.preflight()?;
+        let path = self.create_buffer_file(contents)?;
+        /* Only the first editor is handed over if `on_empty` reopens it */
+        let mut on_spawn = Some(on_spawn);
+        self.run(path, true, |editor, path| {
+            editor.launch(path, |child| on_spawn.take().map_or((), |f| f(child)))
+        })
+    }
+
+    /// Opens the editor without waiting for it to exit, returning an `EditorHandle` to poll, wait on or kill it with, e.g. from an event loop. The buffer is always a file; `pipe_mode` and `wait_on_close` are ignored.
+    pub fn spawn(&mut self, contents: Contents) -> Result<EditorHandle, ScrawlError> {
+        self.preflight()?;
+        let path = self.create_buffer_file(contents)?;
+        self.handle(path, Self::start)
+    }
+
+    /// Like `open`, but returns an `EditSession` bundling the Reader with what happened while editing.
+    pub fn open_session(&mut self, contents: Contents) -> Result<EditSession, ScrawlError> {
+        EditSession::new(self.open(contents)?)
+    }
+
+    /// Like `edit`, but returns an `EditSession` bundling the Reader with what happened while editing.
+    pub fn edit_session<P: AsRef<Path>>(&mut self, path: P) -> Result<EditSession, ScrawlError> {
+        EditSession::new(self.edit(path)?)
+    }
+
+    /// Opens the editor on a writable buffer seeded with `scratch` alongside a read-only one seeded with `reference`, e.g. for writing review comments next to the text under review, and returns the Reader for `scratch` only. The reference is made read-only on disk rather than with `read_only`'s flags, which would apply to both buffers, so editors warn about or refuse saving it; Vim is also passed `-o` to show the two in split windows. The reference buffer is deleted once the editor exits. Not supported with `pipe_mode`, which has no files to open.
+    pub fn open_with_reference(
+        &mut self,
+        reference: Contents,
+        scratch: Contents,
+    ) -> Result<Reader, ScrawlError> {
+        self.with_reference(reference, |editor| editor.open(scratch))
+    }
+
+    /// Opens one editor on all of `paths`, in tabs where the editor can be told to (`-p` for Vim), and returns a Reader for each, in the same order. The files are edited in place like with `edit`, never deleted, and created first if `create` is set. Options applied after the editor closes, such as `post_process` and `on_empty`, only apply to the first file, and only its Reader can `reedit`.
+    pub fn edit_files<I: IntoIterator<Item = PathBuf>>(
+        &mut self,
+        paths: I,
+    ) -> Result<Vec<Reader>, ScrawlError> {
+        let paths = paths.into_iter().collect();
+        self.edit_together(paths, |editor, path| editor.edit(path))
+    }
+
+    /// Opens the editor once for each item, in order, returning the edited values. `on_empty` decides what happens when an item is saved empty.
+    pub fn edit_each<I: IntoIterator<Item = String>>(
+        &mut self,
+        items: I,
+        on_empty: OnEmpty,
+    ) -> Result<Vec<String>, ScrawlError> {
+        edit_each_with(self, items, on_empty, Self::open)
+    }
+
+    /// Opens a file for editing in the User's editor.
+    pub fn edit<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader, ScrawlError> {
+        let path = path.as_ref();
+        self.preflight()?;
+        self.create_edit_file(path)?;
+        self.cursor = self.end_cursor(path);
+        self.run(path.into(), false, |editor, path| {
+            editor.launch(path, |_| ())
+        })
+    }
+}
+
+/// A variant of the Editor struct which runs a closure over the buffer instead of opening an editor. This struct is created by `transform`.
+pub struct TransformState<F: FnOnce(String) -> String> {
+    transform: Option<F>,
+}
+impl<F: FnOnce(String) -> String> EditorState for TransformState<F> {}
+
+impl<F: FnOnce(String) -> String> fmt::Debug for TransformState<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransformState")
+            .field("used", &self.transform.is_none())
+            .finish()
+    }
+}
+
+impl<F: FnOnce(String) -> String> Editor<TransformState<F>> {
+    /* Rewrite the buffer in place; the closure can only run once */
+    fn launch(&mut self, path: &Path) -> Result<Launched, ScrawlError> {
+        let transform = self.state.transform.take().ok_or_else(|| {
+            ScrawlError::FailedToOpenEditor(String::from("the transform has already been run"))
+        })?;
+        let contents = utf8(fs::read(path)?, 0)?;
+        fs::write(path, transform(contents))?;
+        Ok(Launched {
+            editor: TRANSFORM_EDITOR.into(),
+            args: vec![],
+            status: None,
+            stderr: String::new(),
+            relaunch: None,
+        })
+    }
+
+    /// Specify which extension should be used on the temporary file, either as a string (`"rs"` or `".rs"`) or an `Extension`.
+    pub fn ext<S: AsRef<str>>(&mut self, ext: S) -> &mut Self {
+        self.extension = Some(dotted(ext.as_ref()));
+        self
+    }
+
+    /// Seeds a buffer with `contents` and runs the transform over it. Fails with `ScrawlError::FailedToCaptureInput` if the contents aren't UTF-8, or `ScrawlError::FailedToOpenEditor` if the transform was already used.
+    pub fn open(&mut self, contents: Contents) -> Result<Reader, ScrawlError> {
+        let path = self.create_buffer_file(contents)?;
+        self.run(path, true, Self::launch)
+    }
+
+    /// Runs the transform over the file at `path`, saving the result to it.
+    pub fn edit<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader, ScrawlError> {
+        let path = path.as_ref();
+        self.create_edit_file(path)?;
+        self.cursor = self.end_cursor(path);
+        self.run(path.into(), false, Self::launch)
+    }
+}
+
+/* Common extensions, so they can't be mistyped */
+/// Extensions for common file types, for use with `ext()`. Any other extension can be passed to `ext()` as a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extension {
+    /// `.txt`
+    Text,
+    /// `.md`
+    Markdown,
+    /// `.rs`
+    Rust,
+    /// `.toml`
+    Toml,
+    /// `.yaml`
+    Yaml,
+    /// `.json`
+    Json,
+    /// `.html`
+    Html,
+    /// `.js`
+    JavaScript,
+    /// `.py`
+    Python,
+    /// `.sh`
+    Shell,
+    /// `.ini`
+    Ini,
+    /// `.diff`
+    Diff,
+}
+
+impl AsRef<str> for Extension {
+    fn as_ref(&self) -> &str {
+        match self {
+            Extension::Text => ".txt",
+            Extension::Markdown => ".md",
+            Extension::Rust => ".rs",
+            Extension::Toml => ".toml",
+            Extension::Yaml => ".yaml",
+            Extension::Json => ".json",
+            Extension::Html => ".html",
+            Extension::JavaScript => ".js",
+            Extension::Python => ".py",
+            Extension::Shell => ".sh",
+            Extension::Ini => ".ini",
+            Extension::Diff => ".diff",
+        }
+    }
+}
+
+/* User chooses a variant with which to seed the editor contents */
+/// Enum for what to seed the editor's buffer with.
+#[allow(missing_debug_implementations)]
+pub enum Contents<'a> {
+    /// Editor opens an empty file.
+    Empty,
+    /// Editor opens a file with the contents of a String.
+    FromString(&'a dyn AsRef<[u8]>),
+    /// Editor opens a file with the copied contents of a file at specified path.
+    FromFile(&'a dyn AsRef<Path>),
+    /// Editor opens a file with the contents of an owned buffer, for data built on the fly.
+    FromOwnedString(Vec<u8>),
+    /// Editor opens a file with the copied contents of a file at an owned path.
+    FromOwnedPath(PathBuf),
+    /// Editor opens a file with each of these contents one after the other, e.g. instructions, then an existing file, then a footer.
+    Concat(Vec<Contents<'a>>),
+    /// Editor opens a file with the first of these contents whose files can all be read, or an empty file if none can, e.g. a saved draft, else a template.
+    FirstAvailable(Vec<Contents<'a>>),
+    /// Editor opens a file that the function has written, given the open buffer file, e.g. to generate a large buffer without building it in memory first. `pipe_mode` has no buffer file, so there the function is given a scratch file only the user can read, in memory if the `memfd` feature is enabled, and the buffer is built in memory from it.
+    FromWriterFn(WriterFn<'a>),
+}
+
+/// A function that writes a buffer's seed contents into the buffer file, for `Contents::FromWriterFn`.
+pub type WriterFn<'a> = Box<dyn FnOnce(&mut fs::File) -> io::Result<()> + 'a>;
+
+impl<'a> Contents<'a> {
+    /* The first of `choices` that can be seeded from */
+    fn first_available(choices: Vec<Contents<'a>>) -> Contents<'a> {
+        choices
+            .into_iter()
+            .find(|choice| choice.check_sources().is_ok())
+            .unwrap_or(Contents::Empty)
+    }
+
+    /* The seed contents, for when there's no buffer file to copy them into */
+    fn into_bytes(self) -> io::Result<Vec<u8>> {
+        match self {
+            Contents::Empty => Ok(vec![]),
+            Contents::FromString(s) => Ok(s.as_ref().to_vec()),
+            Contents::FromOwnedString(s) => Ok(s),
+            Contents::FromFile(source) => fs::read(source.as_ref()),
+            Contents::FromOwnedPath(source) => fs::read(source),
+            Contents::Concat(parts) => {
+                let mut bytes = Vec::new();
+                for part in parts {
+                    bytes.extend(part.into_bytes()?);
+                }
+                Ok(bytes)
+            }
+            Contents::FirstAvailable(choices) => Contents::first_available(choices).into_bytes(),
+            /* The writer needs a real file, so lend it a scratch one, kept off disk where possible */
+            Contents::FromWriterFn(write) => {
+                let name = unique_file_name(DEFAULT_EXT);
+                let (path, mut file, on_disk) = match memory_file(&name) {
+                    Some((path, file)) => (path, file, false),
+                    None => {
+                        let (path, file) = scratch_file(&name)?;
+                        (path, file, true)
+                    }
+                };
+                let written = write(&mut file).and_then(|_| fs::read(&path));
+                if on_disk {
+                    let _ = fs::remove_file(&path);
+                }
+                written
+            }
+        }
+    }
+
+    /* Writes the seed contents out in order, streaming files rather than reading them whole */
+    fn write_to(self, out: &mut fs::File) -> io::Result<()> {
+        match self {
+            Contents::Empty => Ok(()),
+            Contents::FromString(s) => out.write_all(s.as_ref()),
+            Contents::FromOwnedString(s) => out.write_all(&s),
+            Contents::FromFile(source) => copy_file(source.as_ref(), out),
+            Contents::FromOwnedPath(source) => copy_file(&source, out),
+            Contents::Concat(parts) => parts.into_iter().try_for_each(|part| part.write_to(out)),
+            Contents::FirstAvailable(choices) => Contents::first_available(choices).write_to(out),
+            Contents::FromWriterFn(write) => write(out),
+        }
+    }
+
+    /* The first file the contents are seeded from, if any */
+    fn source_path(&self) -> Option<&Path> {
+        match self {
+            Contents::FromFile(source) => Some(source.as_ref()),
+            Contents::FromOwnedPath(source) => Some(source),
+            Contents::Concat(parts) => parts.iter().find_map(Contents::source_path),
+            Contents::FirstAvailable(choices) => choices
+                .iter()
+                .find(|choice| choice.check_sources().is_ok())
+                .and_then(Contents::source_path),
+            _ => None,
+        }
+    }
+
+    /* Makes sure every file the contents are seeded from can be read, before any buffer is made; FirstAvailable falls back instead */
+    fn check_sources(&self) -> Result<(), ScrawlError> {
+        let source = match self {
+            Contents::FromFile(source) => source.as_ref(),
+            Contents::FromOwnedPath(source) => source,
+            Contents::Concat(parts) => return parts.iter().try_for_each(Contents::check_sources),
+            _ => return Ok(()),
+        };
+        match fs::File::open(source) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(ScrawlError::FailedToCopyToTempFile(source.into())),
+        }
+    }
+}
+
+/* Streams the file at `source` into `out` */
+fn copy_file<W: Write>(source: &Path, out: &mut W) -> io::Result<()> {
+    io::copy(&mut fs::File::open(source)?, out).map(|_| ())
+}
+
+/* Conversions for scrawl::open, strings seed the buffer and paths copy a file */
+impl From<&str> for Contents<'_> {
+    fn from(s: &str) -> Self {
+        Contents::FromOwnedString(s.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for Contents<'_> {
+    fn from(s: String) -> Self {
+        Contents::FromOwnedString(s.into_bytes())
+    }
+}
+
+impl From<&Path> for Contents<'_> {
+    fn from(path: &Path) -> Self {
+        Contents::FromOwnedPath(path.into())
+    }
+}
+
+impl From<PathBuf> for Contents<'_> {
+    fn from(path: PathBuf) -> Self {
+        Contents::FromOwnedPath(path)
+    }
+}
+
+/// After the user closes their editor, it transforms into a Reader object where the input can be retrieved.
+///
+/// The buffer is read by its path once the editor is done, never through a handle opened beforehand, so editors that save by writing a new file and renaming it over the buffer (like vim with `backupcopy=no`) are read correctly. A `~` backup such an editor leaves next to a temporary buffer is deleted along with it.
+#[derive(Debug)]
+pub struct Reader {
+    path: PathBuf,
+    temporary: bool,
+    captured: Option<Vec<u8>>,
+    editor: OsString,
+    args: Vec<OsString>,
+    status: Option<ExitStatus>,
+    extension: String,
+    newline: NewlineStyle,
+    region: Option<(String, String)>,
+    encoding: Option<String>,
+    max_size: Option<u64>,
+    on_read: Option<ReadHook>,
+    shared: Option<Arc<SharedBuffer>>,
+    seed: Option<Vec<u8>>,
+    cache: Mutex<Option<Vec<u8>>>,
+    /* Where `io::Read` has got to, once it has been called */
+    stream: Option<Cursor<Vec<u8>>>,
+    relaunch: Option<Relaunch>,
+}
+
+impl Reader {
+    /* The buffer's contents, from memory if they were captured there */
+    fn contents(&self) -> Result<Vec<u8>, ScrawlError> {
+        let contents = match &self.captured {
+            Some(captured) => {
+                self.check_size(captured.len() as u64)?;
+                self.decoded(captured.clone())
+            }
+            None => self.cached()?,
+        };
+        Ok(self.unwrapped(contents))
+    }
+
+    /* The buffer file's contents, only read from disk the first time */
+    fn cached(&self) -> Result<Vec<u8>, ScrawlError> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(contents) = &*cache {
+            return Ok(contents.clone());
+        }
+        let contents = self.decoded(self.raw()?);
+        *cache = Some(contents.clone());
+        Ok(contents)
+    }
+
+    /* The buffer exactly as the editor left it, before any decoding or unwrapping */
+    fn raw(&self) -> Result<Vec<u8>, ScrawlError> {
+        if let Some(captured) = &self.captured {
+            self.check_size(captured.len() as u64)?;
+            return Ok(captured.clone());
+        }
+        if self.max_size.is_some() {
+            let metadata = fs::metadata(&self.path).map_err(|e| self.io_error(e))?;
+            self.check_size(metadata.len())?;
+        }
+        fs::read(&self.path).map_err(|e| self.io_error(e))
+    }
+
+    /* Fails if a buffer of `size` bytes is over the limit set with max_size */
+    fn check_size(&self, size: u64) -> Result<(), ScrawlError> {
+        match self.max_size {
+            Some(limit) if size > limit => Err(ScrawlError::BufferTooLarge(size, limit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The buffer file is read once, the first time the contents are needed, and every later call is answered from memory. Call this to read it again, e.g. if something other than the editor has changed it since.
+    pub fn reload(&self) {
+        *self.cache.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /* What the buffer started out as, for comparing against its contents */
+    fn seed(&self) -> Vec<u8> {
+        self.unwrapped(self.decoded(self.seed.clone().unwrap_or_default()))
+    }
+
+    /* Decodes a buffer in another encoding (see `Editor::encoding`) into UTF-8 */
+    fn decoded(&self, bytes: Vec<u8>) -> Vec<u8> {
+        match &self.encoding {
+            Some(label) => decode(bytes, label),
+            None => bytes,
+        }
+    }
+
+    /* Drops the wrap header and footer, if there are any */
+    fn unwrapped(&self, bytes: Vec<u8>) -> Vec<u8> {
+        match &self.region {
+            Some(region) => unwrap_region(bytes, region),
+            None => bytes,
+        }
+    }
+
+    /* The file backing the buffer, which in-memory buffers don't have */
+    fn buffer_file(&self) -> Result<&Path, ScrawlError> {
+        match self.captured {
+            Some(_) => Err(ScrawlError::BufferMissing(self.path.clone())),
+            None => Ok(&self.path),
+        }
+    }
+
+    /* The contents as they should be written out */
+    fn output(&self) -> Result<Vec<u8>, ScrawlError> {
+        Ok(convert_newlines(self.contents()?, self.newline))
+    }
+
+    /* Tells "the buffer is gone" apart from other IO failures */
+    fn io_error(&self, e: io::Error) -> ScrawlError {
+        if e.kind() == io::ErrorKind::NotFound && !self.exists() {
+            ScrawlError::BufferMissing(self.path.clone())
+        } else {
+            e.into()
+        }
+    }
+
+    /// Whether the buffer still exists, e.g. it hasn't been deleted or renamed by the editor.
+    pub fn exists(&self) -> bool {
+        self.captured.is_some() || self.path.exists()
+    }
+
+    /// The editor program that was run, or `SCRAWL_TEST_REPLACEMENT` if the test hook stood in for it.
+    pub fn editor_used(&self) -> &OsStr {
+        &self.editor
+    }
+
+    /// The extra arguments the editor was run with, including any flags Scrawl added, but not the buffer's path.
+    pub fn args_used(&self) -> &[OsString] {
+        &self.args
+    }
+
+    /// The buffer's extension, including the leading dot (e.g. `.rs`), or an empty string if it has none.
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// How the editor exited, or `None` if no editor process was run (e.g. under `SCRAWL_TEST_REPLACEMENT`). Scrawl does not treat a failed exit as an error; some editors exit non-zero for reasons unrelated to the buffer, and one that crashed or was killed may have saved the user's work first, which can still be read.
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        self.status
+    }
+
+    /// Whether the editor exited successfully, or no editor process was run. When this is false the buffer holds whatever the editor last saved, which may be incomplete.
+    pub fn editor_succeeded(&self) -> bool {
+        self.status.is_none_or(|status| status.success())
+    }
+
+    /// Read to a vector of bytes.
+    pub fn read(&self) -> Result<Vec<u8>, ScrawlError> {
+        self.contents()
+    }
+
+    /// Returns the buffer byte for byte as the editor left it, without assuming any encoding. This is how to read a buffer opened with `binary(true)`; the `to_string` family all expect text. Unlike `read`, nothing set with `encoding` or `wrap` is undone, so any header and footer are still there.
+    pub fn read_exact_bytes(&self) -> Result<Vec<u8>, ScrawlError> {
+        self.raw()
+    }
+
+    /// Returns the buffer as a String, exactly as the editor saved it (including its line endings). Fails with `ScrawlError::FailedToCaptureInput`, holding the byte offset of the problem, if the buffer isn't valid UTF-8.
+    pub fn to_string(&self) -> Result<String, ScrawlError> {
+        Ok(self.on_read(utf8(self.contents()?, 0)?))
+    }
+
+    /// Returns the buffer as a String, replacing any invalid UTF-8 with `U+FFFD`.
+    pub fn to_string_lossy(&self) -> Result<String, ScrawlError> {
+        let text = String::from_utf8_lossy(&self.contents()?).into_owned();
+        Ok(self.on_read(text))
+    }
+
+    /// Returns the buffer as a String with `\r\n` and lone `\r` line endings converted to `\n`.
+    pub fn to_string_normalized(&self) -> Result<String, ScrawlError> {
+        let contents = self.to_string()?;
+        Ok(contents.replace("\r\n", "\n").replace('\r', "\n"))
+    }
+
+    /// Returns the buffer as a String, honoring a byte order mark: a UTF-8 BOM is stripped, and UTF-16 (LE or BE) is transcoded. Buffers without a BOM are read as UTF-8. Useful for editors such as Notepad which may save with a BOM.
+    ///
+    /// The offset in a `ScrawlError::FailedToCaptureInput` counts a stripped UTF-8 BOM, so it still points into the file as saved.
+    pub fn to_string_decoded(&self) -> Result<String, ScrawlError> {
+        let bytes = self.contents()?;
+        let text = match bytes.as_slice() {
+            [0xEF, 0xBB, 0xBF, rest @ ..] => utf8(rest.to_vec(), 3)?,
+            [0xFF, 0xFE, rest @ ..] => decode_utf16(rest, u16::from_le_bytes)?,
+            [0xFE, 0xFF, rest @ ..] => decode_utf16(rest, u16::from_be_bytes)?,
+            _ => utf8(bytes, 0)?,
+        };
+        Ok(self.on_read(text))
+    }
+
+    /* Runs the Editor's `on_read` callback, if it has one */
+    fn on_read(&self, text: String) -> String {
+        match &self.on_read {
+            Some(ReadHook(f)) => f(text),
+            None => text,
+        }
+    }
+
+    /// Returns the buffer as a String with every line that starts with `prefix` (ignoring leading whitespace) removed, like git does with `#` lines in a commit message.
+    pub fn strip_comments(&self, prefix: &str) -> Result<String, ScrawlError> {
+        let contents = self.to_string()?;
+        if prefix.is_empty() {
+            return Ok(contents);
+        }
+        Ok(contents
+            .split_inclusive('\n')
+            .filter(|line| !line.trim_start().starts_with(prefix))
+            .collect())
+    }
+
+    /// Like `strip_comments`, with the comment prefix picked from the buffer's extension: `//` for Rust, JavaScript and JSON, `;` for INI, `--` for SQL and Lua, and `#` for everything else (shell, YAML, TOML, Python, plain text, ...). Markdown and HTML have no line comments and are returned unchanged. Call `strip_comments` directly to use a different prefix.
+    pub fn strip_comments_for_ext(&self) -> Result<String, ScrawlError> {
+        self.strip_comments(comment_prefix(&self.extension))
+    }
+
+    /// Splits the buffer into entries on every line that is exactly `delimiter` (e.g. `---`), trimming each entry, so several values can be collected in one edit. Empty entries before the first or after the last delimiter are left out, e.g. for a buffer that ends with one, but empty entries in between are kept. Fails with `ScrawlError::FailedToCaptureInput` if the buffer isn't valid UTF-8.
+    pub fn split_into_entries(&self, delimiter: &str) -> Result<Vec<String>, ScrawlError> {
+        let contents = self.to_string()?;
+        let mut entries = vec![String::new()];
+        for line in contents.split_inclusive('\n') {
+            let trimmed = line.strip_suffix('\n').unwrap_or(line);
+            if trimmed.strip_suffix('\r').unwrap_or(trimmed) == delimiter {
+                entries.push(String::new());
+            } else if let Some(entry) = entries.last_mut() {
+                entry.push_str(line);
+            }
+        }
+        let mut entries: Vec<String> = entries.iter().map(|e| e.trim().to_string()).collect();
+        while entries.last().is_some_and(String::is_empty) {
+            entries.pop();
+        }
+        let leading = entries.iter().take_while(|e| e.is_empty()).count();
+        Ok(entries.split_off(leading))
+    }
+
+    /// Returns the buffer as a BufReader. The BufReader reads the buffer file directly, and a temporary buffer is deleted when the Reader is dropped: on Unix the open handle keeps reading the deleted file, elsewhere reads may fail. Keep the Reader alive while the BufReader is in use, or use `into_reader` instead.
+    /// Fails with `ScrawlError::BufferMissing` for output captured in memory (see `pipe_mode`), which has no file.
+    pub fn to_bufreader(&self) -> Result<BufReader<fs::File>, ScrawlError> {
+        let file = fs::File::open(self.buffer_file()?).map_err(|e| self.io_error(e))?;
+        Ok(BufReader::new(file))
+    }
+
+    /// Calls `f` with the path of the buffer file, e.g. to run a formatter over it before reading it back; the file is kept for at least as long as `f` runs, and is read again afterwards. Fails with `ScrawlError::BufferMissing` if the file is already gone, or for output captured in memory (see `pipe_mode`), which has no file.
+    pub fn with_path<R, F: FnOnce(&Path) -> R>(&self, f: F) -> Result<R, ScrawlError> {
+        let path = self.buffer_file()?;
+        if !path.exists() {
+            return Err(ScrawlError::BufferMissing(path.into()));
+        }
+        let result = f(path);
+        self.reload();
+        Ok(result)
+    }
+
+    /// Opens the buffer in the editor again, run the same way as before (command line, environment, working directory and `command_hook`), and returns the Reader for the result, e.g. to let the user fix what they wrote. The stdio handles given to `stdin`, `stdout` and `stderr` were used up by the first run, so the editor inherits this process's instead. Nothing is re-seeded, so the user carries on from what they saved, and editors that remember positions in a file open where they left off. Options that apply after the editor closes, like `post_process` and `on_empty`, aren't applied again. Under `SCRAWL_TEST_REPLACEMENT` the buffer is replaced again instead. Fails with `ScrawlError::BufferMissing` for output captured in memory (see `pipe_mode`), and with `ScrawlError::FailedToOpenEditor` if no editor made the buffer, e.g. for `transform`.
+    pub fn reedit(mut self) -> Result<Reader, ScrawlError> {
+        let path = self.buffer_file()?.to_path_buf();
+        if !apply_test_replacement(&path)? {
+            let Some(relaunch) = &self.relaunch else {
+                let editor = self.editor.to_string_lossy();
+                return Err(ScrawlError::FailedToOpenEditor(format!(
+                    "{editor}: there is no editor to reopen"
+                )));
+            };
+            let before = self.raw().ok();
+            let (status, stderr) = relaunch.run()?;
+            self.status = Some(status);
+            /* As when it was first opened, an editor that failed without touching the buffer most likely didn't like its command line */
+            if !status.success() && !stderr.is_empty() && self.raw().ok() == before {
+                return Err(ScrawlError::FailedToOpenEditor(format!(
+                    "{}: exited with {status}: {}",
+                    self.editor.to_string_lossy(),
+                    stderr.trim_end()
+                )));
+            }
+        }
+        self.reload();
+        self.stream = None;
+        Ok(self)
+    }
+
+    /// Returns a guard that keeps the buffer file from being deleted until every clone of it is dropped, even once the Reader is, e.g. to hand the path to a spawned thread or async task. A temporary buffer is deleted when the last guard or the Reader goes, whichever is later, and `close` leaves it to the guards if any are left. Fails with `ScrawlError::BufferMissing` for output captured in memory (see `pipe_mode`), which has no file.
+    pub fn keep_alive(&mut self) -> Result<BufferGuard, ScrawlError> {
+        let path = self.buffer_file()?.to_path_buf();
+        /* Hand a temporary buffer over to a SharedBuffer, which the Reader and its guards share */
+        if self.temporary && self.shared.is_none() {
+            self.shared = Some(Arc::new(SharedBuffer {
+                path: path.clone(),
+                memory: None,
+            }));
+        }
+        Ok(BufferGuard {
+            path,
+            _buffer: self.shared.clone(),
+        })
+    }
+
+    /// Consumes the Reader and returns its contents as an in-memory reader, which is unaffected by the buffer file being cleaned up.
+    pub fn into_reader(self) -> Result<impl Read, ScrawlError> {
+        Ok(Cursor::new(self.contents()?))
+    }
+
+    /// Streams the buffer into `writer` with `io::copy`, without loading it into memory first, and returns how many bytes were written. Writes the same bytes `read` returns; a buffer that is already in memory (captured with `pipe_mode`, read before, or cut out of a `wrap`) is written from there.
+    pub fn to_writer<W: Write + ?Sized>(&self, writer: &mut W) -> Result<u64, ScrawlError> {
+        let cached = self
+            .cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some();
+        if cached || self.captured.is_some() || self.region.is_some() || self.encoding.is_some() {
+            let contents = self.contents()?;
+            writer.write_all(&contents)?;
+            return Ok(contents.len() as u64);
+        }
+        let mut file = fs::File::open(&self.path).map_err(|e| self.io_error(e))?;
+        if self.max_size.is_some() {
+            self.check_size(file.metadata()?.len())?;
+        }
+        Ok(io::copy(&mut file, writer)?)
+    }
+
+    /// The number of characters (Unicode scalar values, not bytes) in the buffer. Fails with `ScrawlError::FailedToCaptureInput` if it isn't valid UTF-8.
+    pub fn char_count(&self) -> Result<usize, ScrawlError> {
+        Ok(self.to_string()?.chars().count())
+    }
+
+    /// The number of words in the buffer, where a word is any run of characters between Unicode whitespace, so punctuation counts as part of the word it is attached to (`don't stop.` is two words). Fails with `ScrawlError::FailedToCaptureInput` if it isn't valid UTF-8.
+    pub fn word_count(&self) -> Result<usize, ScrawlError> {
+        Ok(self.to_string()?.split_whitespace().count())
+    }
+
+    /// The number of lines in the buffer: one per `\n` (or `\r\n`), plus a final line with no line ending. An empty buffer has no lines. Fails with `ScrawlError::FailedToCaptureInput` if it isn't valid UTF-8.
+    pub fn line_count(&self) -> Result<usize, ScrawlError> {
+        let contents = self.to_string()?;
+        let terminators = contents.matches('\n').count();
+        let unterminated = !contents.is_empty() && !contents.ends_with('\n');
+        Ok(terminators + usize::from(unterminated))
+    }
+
+    /// Whether the buffer's contents differ from what it was seeded with.
+    pub fn was_modified(&self) -> Result<bool, ScrawlError> {
+        Ok(self.seed() != self.contents()?)
+    }
+
+    /// Returns a unified diff of the buffer against what it was seeded with, or an empty string if nothing changed.
+    #[cfg(feature = "diff")]
+    pub fn diff(&self) -> Result<String, ScrawlError> {
+        let seed = self.seed();
+        let seed = String::from_utf8_lossy(&seed);
+        let buffer = self.to_string()?;
+        if seed == buffer {
+            return Ok(String::new());
+        }
+        Ok(
+            similar::TextDiff::from_lines(seed.as_ref(), buffer.as_str())
+                .unified_diff()
+                .header("original", "edited")
+                .to_string(),
+        )
+    }
+
+    /// Saves the contents to a file at the specified path, with the line endings set by `newline`.
+    pub fn to_file<P: AsRef<Path>>(&self, path: &P) -> Result<u64, ScrawlError> {
+        if self.captured.is_none()
+            && self.region.is_none()
+            && self.encoding.is_none()
+            && self.newline == NewlineStyle::Preserve
+        {
+            return fs::copy(&self.path, path.as_ref()).map_err(|e| self.io_error(e));
+        }
+        let contents = self.output()?;
+        fs::write(path, &contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    /// Saves the contents to `path` like `to_file` and returns them like `to_string`, reading the buffer only once, e.g. to keep a copy of what the user wrote while also using it. The file is written before the contents are checked to be UTF-8, so it is saved even if this fails with `ScrawlError::FailedToCaptureInput`.
+    pub fn tee<P: AsRef<Path>>(&self, path: &P) -> Result<String, ScrawlError> {
+        let contents = self.contents()?;
+        match self.newline {
+            NewlineStyle::Preserve => fs::write(path, &contents)?,
+            style => fs::write(path, convert_newlines(contents.clone(), style))?,
+        }
+        Ok(self.on_read(utf8(contents, 0)?))
+    }
+
+    /// Like `to_file`, but fails with `ScrawlError::DestinationExists` instead of overwriting a file that is already at `path`.
+    pub fn to_file_no_clobber<P: AsRef<Path>>(&self, path: &P) -> Result<u64, ScrawlError> {
+        let path = path.as_ref();
+        let contents = self.output()?;
+        /* create_new so a file appearing in the meantime is never overwritten either */
+        let mut dest = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(ScrawlError::DestinationExists(path.into()))
+            }
+            dest => dest?,
+        };
+        dest.write_all(&contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    /// Appends the contents to the file at the specified path, creating it if it doesn't exist, e.g. to collect several editing sessions into one journal. Returns the number of bytes appended.
+    pub fn append_to_file<P: AsRef<Path>>(&self, path: &P) -> Result<u64, ScrawlError> {
+        let contents = self.output()?;
+        fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)?
+            .write_all(&contents)?;
+        Ok(contents.len() as u64)
+    }
+
+    /// Saves the contents into `dir` as `name`, or under the buffer's own unique name if `None`. If the name is taken a counter is appended (`note_1.md`, `note_2.md`, ...). Returns the path that was written.
+    pub fn save_to_dir<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        name: Option<&str>,
+    ) -> Result<PathBuf, ScrawlError> {
+        let generated;
+        let name = match (name, self.path.file_name()) {
+            (Some(name), _) => Path::new(name),
+            (None, Some(buffer_name)) => Path::new(buffer_name),
+            (None, None) => {
+                generated = unique_file_name(DEFAULT_EXT);
+                Path::new(&generated)
+            }
+        };
+        let stem = name.file_stem().unwrap_or_default();
+        let ext = name.extension();
+
+        let contents = self.output()?;
+        let mut i: u64 = 0;
+        loop {
+            let mut file_name = stem.to_os_string();
+            if i > 0 {
+                file_name.push(format!("_{i}"));
+            }
+            if let Some(ext) = ext {
+                file_name.push(".");
+                file_name.push(ext);
+            }
+
+            /* create_new so a file appearing in the meantime is never overwritten */
+            let path = dir.as_ref().join(file_name);
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut dest) => {
+                    dest.write_all(&contents)?;
+                    return Ok(path);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => i += 1,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Deletes the temporary buffer now, reporting any failure instead of ignoring it like dropping the Reader does. Does nothing for a file opened with `edit`, or for a `reuse_buffer` buffer that is still in use elsewhere.
+    pub fn close(mut self) -> Result<(), ScrawlError> {
+        /* Drop won't try again either way */
+        let temporary = std::mem::replace(&mut self.temporary, false);
+        let shared = self.shared.take().map(Arc::into_inner);
+        if let Some(shared) = shared {
+            /* Only the last user of a shared buffer deletes it */
+            if let Some(shared) = shared {
+                shared.remove()?;
+            }
+        } else if temporary {
+            remove_buffer(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/* The line comment token for files with extension `ext`, or "" for formats without one */
+fn comment_prefix(ext: &str) -> &'static str {
+    match ext.trim_start_matches('.').to_ascii_lowercase().as_str() {
+        "rs" | "js" | "mjs" | "ts" | "json" | "jsonc" | "c" | "h" | "cpp" | "hpp" | "go"
+        | "java" | "kt" | "swift" => "//",
+        "ini" | "el" | "lisp" => ";",
+        "sql" | "lua" | "hs" => "--",
+        "md" | "markdown" | "html" | "htm" | "xml" => "",
+        _ => "#",
+    }
+}
+
+/* Decoding helpers for Reader's to_string methods */
+fn utf8(bytes: Vec<u8>, offset: usize) -> Result<String, ScrawlError> {
+    String::from_utf8(bytes)
+        .map_err(|e| ScrawlError::FailedToCaptureInput(offset + e.utf8_error().valid_up_to()))
+}
+
+fn invalid_data<E: fmt::Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> io::Result<String> {
+    let pairs = bytes.chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return Err(invalid_data("UTF-16 buffer has an odd number of bytes"));
+    }
+    let units: Vec<u16> = pairs.map(|pair| to_unit([pair[0], pair[1]])).collect();
+    String::from_utf16(&units).map_err(invalid_data)
+}
+
+/* An editor running in the background */
+/// A running editor, returned by `spawn`. Once it exits, its Reader can be taken with `try_wait` or `wait`. Dropping the handle doesn't stop the editor, but does delete its buffer, so `wait` or `kill` it first.
+#[derive(Debug)]
+pub struct EditorHandle {
+    child: Option<Child>,
+    reader: Option<Reader>,
+}
+
+impl EditorHandle {
+    /// Returns the Reader if the editor has exited, or `None` if it is still running. Fails if the Reader was already taken.
+    pub fn try_wait(&mut self) -> Result<Option<Reader>, ScrawlError> {
+        let status = match &mut self.child {
+            Some(child) => match child.try_wait()? {
+                Some(status) => Some(status),
+                None => return Ok(None),
+            },
+            None => None,
+        };
+        self.finish(status).map(Some)
+    }
+
+    /// Waits for the editor to exit and returns the Reader. Fails if the Reader was already taken by `try_wait`.
+    pub fn wait(mut self) -> Result<Reader, ScrawlError> {
+        let status = match &mut self.child {
+            Some(child) => Some(child.wait()?),
+            None => None,
+        };
+        self.finish(status)
+    }
+
+    /// Kills the editor. The buffer is kept, so whatever the user had saved can still be read with `wait`.
+    pub fn kill(&mut self) -> Result<(), ScrawlError> {
+        if let Some(child) = &mut self.child {
+            child.kill()?;
+        }
+        Ok(())
+    }
+
+    /// Reads the buffer as it is right now, without waiting for the editor to exit, e.g. to update a live preview. Most editors only write the buffer when the user saves, so this is whatever was last saved, not what is on screen. Fails if the Reader was already taken.
+    pub fn current_contents(&self) -> Result<Vec<u8>, ScrawlError> {
+        let reader = self.reader.as_ref().ok_or_else(reader_taken)?;
+        reader.reload();
+        reader.read()
+    }
+
+    /* Hands over the Reader, exactly once */
+    fn finish(&mut self, status: Option<ExitStatus>) -> Result<Reader, ScrawlError> {
+        let mut reader = self.reader.take().ok_or_else(reader_taken)?;
+        /* Nothing read while the editor was running is current any more */
+        reader.reload();
+        reader.status = status;
+        Ok(reader)
+    }
+}
+
+/* The error for using an EditorHandle after its Reader was handed over */
+fn reader_taken() -> io::Error {
+    io::Error::other("the editor's Reader was already taken")
+}
+
+/// Keeps a Reader's buffer file around, made by `Reader::keep_alive`. Clones share the file, which is deleted once the last of them and the Reader are dropped; a file that wasn't temporary is never deleted.
+#[derive(Debug, Clone)]
+pub struct BufferGuard {
+    path: PathBuf,
+    /* Only held, so the buffer outlives the Reader */
+    _buffer: Option<Arc<SharedBuffer>>,
+}
+
+impl BufferGuard {
+    /// The path of the buffer file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for BufferGuard {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/* Everything about an edit in one place */
+/// The result of `open_session` or `edit_session`: the Reader, along with which editor ran, how it exited, and whether the buffer changed.
+#[derive(Debug)]
+pub struct EditSession {
+    /// Reads what the user wrote.
+    pub reader: Reader,
+    /// The editor that was run, as `Reader::editor_used`.
+    pub editor_used: OsString,
+    /// How the editor exited, or `None` if no editor process was run, as `Reader::exit_status`.
+    pub status: Option<ExitStatus>,
+    /// Whether the buffer differs from what it was seeded with, as `Reader::was_modified`.
+    pub modified: bool,
+    /// The buffer file, which is empty for output captured in memory (see `pipe_mode`). A temporary buffer is deleted when `reader` is dropped.
+    pub path: PathBuf,
+}
+
+impl EditSession {
+    /* Gathers up the session's details from the Reader */
+    fn new(reader: Reader) -> Result<Self, ScrawlError> {
+        Ok(EditSession {
+            editor_used: reader.editor.clone(),
+            status: reader.status,
+            modified: reader.was_modified()?,
+            path: reader.path.clone(),
+            reader,
+        })
+    }
+}
+
+/* Deletes a temporary buffer, and the backup an editor that saves by renaming may have left beside it */
+fn remove_buffer(path: &Path) -> io::Result<()> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push("~");
+    let _ = fs::remove_file(backup);
+    fs::remove_file(path)?;
+
+    /* Don't leave an empty .scrawl directory behind in a project */
+    if let Some(dir) = path.parent().filter(|dir| dir.ends_with(PROJECT_DIR)) {
+        let _ = fs::remove_dir(dir);
+    }
+    Ok(())
+}
+
+/* A buffer kept by `reuse_buffer` or `memory_backed`, deleted or freed once nothing uses it */
+#[derive(Debug)]
+struct SharedBuffer {
+    path: PathBuf,
+    /* The memory file behind `path`, which has nothing on disk to delete */
+    memory: Option<fs::File>,
+}
+
+impl SharedBuffer {
+    /* Deletes the buffer now, leaving Drop nothing to do */
+    fn remove(mut self) -> io::Result<()> {
+        let path = std::mem::take(&mut self.path);
+        match self.memory {
+            Some(_) => Ok(()),
+            None => remove_buffer(&path),
+        }
+    }
+}
+
+impl Drop for SharedBuffer {
+    fn drop(&mut self) {
+        if self.memory.is_none() && !self.path.as_os_str().is_empty() {
+            let _ = remove_buffer(&self.path);
+        }
+    }
+}
+
+/// Streams the same bytes as `Reader::read`, so a Reader can be passed anywhere an `impl Read` is wanted, e.g. `serde_json::from_reader(reader)` or `io::copy(&mut reader, &mut sink)`. The contents are loaded on the first call and later calls carry on from there; `reload` doesn't rewind it. Since `reader.read()` is the method returning everything at once, call it as `Read::read(&mut reader, buf)` directly.
+impl Read for Reader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.stream.is_none() {
+            let contents = self.contents().map_err(|e| match e {
+                ScrawlError::Io(e) => e,
+                e => io::Error::other(e),
+            })?;
+            self.stream = Some(Cursor::new(contents));
+        }
+        self.stream.as_mut().expect("loaded above").read(buf)
+    }
+}
+
+/* Delete our temporary file to clean up */
+impl Drop for Reader {
+    fn drop(&mut self) {
+        /* Only clean up files we created, leaving shared buffers to SharedBuffer */
+        if self.temporary && self.shared.is_none() {
+            let _ = remove_buffer(&self.path);
         }
     }
 }
@@ -0,0 +1,156 @@
+//! # Error
+//! The error type returned by Scrawl when opening an editor or capturing its output fails.
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unstable_features,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+/* Standard Library */
+use std::{error::Error, ffi::OsString, fmt, io, path::PathBuf};
+
+/// Enum of all the ways Scrawl can fail.
+#[derive(Debug)]
+pub enum ScrawlError {
+    /// The temporary file used as the editor's buffer could not be created; holds the directories that were tried.
+    FailedToCreateTempfile(Vec<PathBuf>),
+    /// The file to be edited did not exist and could not be created; holds its path.
+    FailedToCreateFile(PathBuf),
+    /// The editor program could not be opened, or exited unsuccessfully without changing the buffer; holds the editor's name and the OS error or what it wrote to stderr.
+    FailedToOpenEditor(String),
+    /// The buffer was not valid UTF-8; holds the byte offset of the first invalid sequence.
+    FailedToCaptureInput(usize),
+    /// The seed file could not be copied into the temporary buffer.
+    FailedToCopyToTempFile(PathBuf),
+    /// No text editing program could be found; holds the programs that were tried.
+    EditorNotFound(Vec<OsString>),
+    /// `require_interactive` was set, but stdin or stdout isn't a terminal.
+    NotInteractive,
+    /// The user saved the buffer empty, and `on_empty` was `OnEmpty::Error`.
+    EmptyBuffer,
+    /// The buffer had a line matching the sentinel set with `abort_on`, so the user cancelled.
+    Aborted,
+    /// The buffer file no longer exists, e.g. the editor deleted or renamed it; holds its path.
+    BufferMissing(PathBuf),
+    /// The buffer was bigger than the limit set with `max_size`; holds its size and the limit, in bytes.
+    BufferTooLarge(u64, u64),
+    /// The `post_process` command could not be run or exited unsuccessfully; holds its name and the OS error or what it wrote to stderr.
+    PostProcessFailed(String),
+    /// The Reader was asked not to overwrite an existing file; holds its path.
+    DestinationExists(PathBuf),
+    /// An edited config file still didn't parse when the user gave up; holds the parser's error.
+    InvalidConfig(String),
+    /// The encoding set with `Editor::encoding` isn't one `encoding_rs` knows; holds its label.
+    #[cfg(feature = "encoding_rs")]
+    UnknownEncoding(String),
+    /// A template placeholder had no value to fill it with.
+    UnresolvedPlaceholder(String),
+    /// An underlying IO operation failed.
+    Io(io::Error),
+}
+
+/* Predicates, so callers don't need to match on every variant */
+impl ScrawlError {
+    /// Whether no text editing program could be found.
+    pub fn is_editor_not_found(&self) -> bool {
+        matches!(self, ScrawlError::EditorNotFound(_))
+    }
+
+    /// Whether an editor was found but could not be started.
+    pub fn is_open_failure(&self) -> bool {
+        matches!(self, ScrawlError::FailedToOpenEditor(_))
+    }
+
+    /// Whether the buffer could not be read as text.
+    pub fn is_capture_failure(&self) -> bool {
+        matches!(self, ScrawlError::FailedToCaptureInput(_))
+    }
+
+    /// Whether an underlying IO operation failed.
+    pub fn is_io(&self) -> bool {
+        matches!(self, ScrawlError::Io(_))
+    }
+}
+
+impl fmt::Display for ScrawlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScrawlError::FailedToCreateTempfile(tried) => {
+                let tried: Vec<_> = tried.iter().map(|p| p.display().to_string()).collect();
+                write!(
+                    f,
+                    "Could not create a temporary file, tried: {}",
+                    tried.join(", ")
+                )
+            }
+            ScrawlError::FailedToCreateFile(path) => {
+                write!(f, "Could not create {}", path.display())
+            }
+            ScrawlError::FailedToOpenEditor(editor) => write!(f, "Could not open editor: {editor}"),
+            ScrawlError::FailedToCaptureInput(offset) => write!(
+                f,
+                "Could not capture input from the editor, invalid UTF-8 at byte {offset}"
+            ),
+            ScrawlError::FailedToCopyToTempFile(source) => {
+                write!(f, "Could not copy {} to a temporary file", source.display())
+            }
+            ScrawlError::EditorNotFound(tried) => {
+                let tried: Vec<_> = tried.iter().map(|p| p.to_string_lossy()).collect();
+                write!(
+                    f,
+                    "Could not find a text editing program, tried: {}; set VISUAL or EDITOR to the editor to use",
+                    tried.join(", ")
+                )
+            }
+            ScrawlError::NotInteractive => {
+                write!(
+                    f,
+                    "Could not open an editor, stdin and stdout must be terminals"
+                )
+            }
+            ScrawlError::EmptyBuffer => write!(f, "Could not use the buffer, it was saved empty"),
+            ScrawlError::Aborted => write!(f, "Could not use the buffer, the user aborted"),
+            ScrawlError::BufferMissing(path) => {
+                write!(f, "The buffer {} no longer exists", path.display())
+            }
+            ScrawlError::BufferTooLarge(size, limit) => write!(
+                f,
+                "Could not read the buffer, it is {size} bytes and the limit is {limit}"
+            ),
+            ScrawlError::PostProcessFailed(command) => {
+                write!(f, "Could not post-process the buffer: {command}")
+            }
+            ScrawlError::DestinationExists(path) => {
+                write!(f, "Could not save to {}, it already exists", path.display())
+            }
+            ScrawlError::InvalidConfig(e) => write!(f, "Could not parse the config: {e}"),
+            #[cfg(feature = "encoding_rs")]
+            ScrawlError::UnknownEncoding(label) => write!(f, "Could not find the encoding {label}"),
+            ScrawlError::UnresolvedPlaceholder(name) => {
+                write!(f, "No value for template placeholder {{{{{name}}}}}")
+            }
+            ScrawlError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for ScrawlError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ScrawlError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScrawlError {
+    fn from(e: io::Error) -> Self {
+        ScrawlError::Io(e)
+    }
+}
@@ -1,5 +1,21 @@
 //! # Scrawl
 //! A library for opening a file for editing in a text editor and capturing the result as a String
+//!
+//! ## Choosing an Editor
+//! Scrawl opens the first of `SCRAWL_EDITOR`, `VISUAL` and `EDITOR` that is set, falling back to a list of common editors. Values are split into a program and its arguments like a shell would, so `EDITOR="code --wait"` works. A relative path such as `EDITOR=./scripts/edit.sh` is taken from the directory the process was in when the Editor was made, even if the editor is run elsewhere with `current_dir`; if the file exists but isn't executable, opening fails with `ScrawlError::FailedToOpenEditor` saying so. `SCRAWL_EDITOR` changes the editor for Scrawl alone, without affecting git, crontab, etc.
+//!
+//! ## Testing
+//! Setting the `SCRAWL_TEST_REPLACEMENT` environment variable stops Scrawl from launching an editor at all; instead the buffer is overwritten with the variable's value, as if the user had typed it and saved. This is intended for tests, so code that uses Scrawl can run deterministically in CI.
+//!
+//! ```no_run
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! std::env::set_var("SCRAWL_TEST_REPLACEMENT", "Blue");
+//! let input = scrawl::with("What is your favorite color")?;
+//! assert_eq!(input.to_string()?, "Blue");
+//! #   Ok(())
+//! # }
+//! ```
 #![deny(
     missing_docs,
     missing_debug_implementations,
@@ -13,12 +29,21 @@
 )]
 
 /* Standard Library */
-use std::error::Error;
+use std::collections::HashMap;
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+use std::fs;
 use std::path::Path;
 
 /* Internal Modules */
 pub mod editor;
-pub use editor::Contents;
+pub use editor::{Contents, Extension, FlagInjection, NewlineStyle, OnEmpty, Os};
+pub mod error;
+pub use error::ScrawlError;
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+pub mod config;
+mod logging;
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+pub use config::ConfigFormat;
 
 /* Convenience functions */
 /// New opens an empty text buffer in an editor and returns a Readable struct on success.
@@ -34,11 +59,51 @@ pub use editor::Contents;
 /// #   Ok(())
 /// # }
 /// ```
-pub fn new() -> Result<editor::Reader, Box<dyn Error>> {
+pub fn new() -> Result<editor::Reader, ScrawlError> {
     editor::new().open(Contents::Empty)
 }
 
-/// With opens a text buffer with the provided contents in an editor. Returns a Readble struct on success.
+/// NewOrStdin behaves like `new` when stdin is a terminal. Otherwise, as when run in a pipe or in CI, no editor is opened and the Reader holds everything read from stdin instead, the way `git commit -F -` would.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     /* `echo hi | my-tool` reads "hi\n", `my-tool` opens the user's editor */
+///     let input = scrawl::new_or_stdin()?;
+///     println!("{}", input.to_string()?);
+/// #   Ok(())
+/// # }
+/// ```
+pub fn new_or_stdin() -> Result<editor::Reader, ScrawlError> {
+    match editor::read_piped_stdin(&[])? {
+        Some(reader) => Ok(reader),
+        None => new(),
+    }
+}
+
+/// Open opens a text buffer seeded with anything that converts into `Contents`: strings become the buffer's text, and paths have their file copied in. Returns a Readable struct on success.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// # use std::path::Path;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     /* Opens the user's editor, buffer pre-filled with a string */
+///     let input = scrawl::open("hello")?;
+///     println!("{}", input.to_string()?);
+///
+///     /* Opens the user's editor, buffer pre-filled with the contents of a file */
+///     let input = scrawl::open(Path::new("foo.txt"))?;
+///     println!("{}", input.to_string()?);
+/// #   Ok(())
+/// # }
+/// ```
+pub fn open<'a, C: Into<Contents<'a>>>(contents: C) -> Result<editor::Reader, ScrawlError> {
+    editor::new().open(contents.into())
+}
+
+/// With opens a text buffer with the provided contents in an editor, which can be anything holding bytes, such as a `&str`, `String` or `Vec<u8>`, by value or by reference. Returns a Readble struct on success.
 ///
 /// # Example
 /// ```no_run
@@ -46,16 +111,112 @@ pub fn new() -> Result<editor::Reader, Box<dyn Error>> {
 /// # use std::io::Read;
 /// # fn main() -> Result<(), Box<dyn Error>> {
 ///     /* Opens the user's editor, buffer pre-filled with custom content */
-///     let input = scrawl::with(&"What is your favorite color")?;
+///     let input = scrawl::with("What is your favorite color")?;
+///     println!("{}", input.to_string()?);
+/// #   Ok(())
+/// # }
+/// ```
+pub fn with<U: AsRef<[u8]>>(input: U) -> Result<editor::Reader, ScrawlError> {
+    editor::new().open(Contents::FromString(&input))
+}
+
+/// WithOrStdin behaves like `with` when stdin is a terminal, otherwise it reads stdin instead of opening an editor, like `new_or_stdin`.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     let input = scrawl::with_or_stdin("What is your favorite color")?;
+///     println!("{}", input.to_string()?);
+/// #   Ok(())
+/// # }
+/// ```
+pub fn with_or_stdin<U: AsRef<[u8]>>(input: U) -> Result<editor::Reader, ScrawlError> {
+    match editor::read_piped_stdin(input.as_ref())? {
+        Some(reader) => Ok(reader),
+        None => with(input),
+    }
+}
+
+/// WithTemplate fills each `{{name}}` placeholder in the template with its value, then opens the result in an editor. Returns a Readable struct on success, or an error if a placeholder has no value.
+///
+/// # Example
+/// ```no_run
+/// # use std::collections::HashMap;
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     /* Opens the user's editor, buffer pre-filled with the filled in template */
+///     let values = HashMap::from([("name", "Amy"), ("color", "purple")]);
+///     let input = scrawl::with_template("Name: {{name}}\nColor: {{color}}", &values)?;
 ///     println!("{}", input.to_string()?);
 /// #   Ok(())
 /// # }
 /// ```
-pub fn with<U: AsRef<[u8]>>(input: &U) -> Result<editor::Reader, Box<dyn Error>> {
-    editor::new().open(Contents::FromString(input))
+pub fn with_template(
+    template: &str,
+    values: &HashMap<&str, &str>,
+) -> Result<editor::Reader, ScrawlError> {
+    let filled = fill_template(template, values)?;
+    editor::new().open(Contents::FromOwnedString(filled.into_bytes()))
 }
 
-/// FromFile opens a text buffer with the content of the provided file in an editor. Returns a Readble struct on success.
+/* Replaces {{name}} tokens, an unmatched {{ is left as is */
+fn fill_template(template: &str, values: &HashMap<&str, &str>) -> Result<String, ScrawlError> {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = values
+            .get(name)
+            .ok_or_else(|| ScrawlError::UnresolvedPlaceholder(name.into()))?;
+        filled.push_str(&rest[..start]);
+        filled.push_str(value);
+        rest = &rest[start + len + 4..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// Confirm asks the user to confirm something irreversible by typing `YES` into an editor, which is harder to do by accident than pressing a key. The prompt is shown as `#` comments; returns whether what is left once they are stripped, trimmed, is exactly `YES`.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     if scrawl::confirm("Delete every branch except main?")? {
+///         println!("Deleting...");
+///     }
+/// #   Ok(())
+/// # }
+/// ```
+pub fn confirm(prompt: &str) -> Result<bool, ScrawlError> {
+    let mut seed: String = prompt.lines().map(|line| format!("# {line}\n")).collect();
+    seed.push_str("# Type YES to confirm\n");
+    let reader = editor::new().open(Contents::FromOwnedString(seed.into_bytes()))?;
+    Ok(reader.strip_comments("#")?.trim() == "YES")
+}
+
+/// EditEach opens each item in an editor in turn, and returns the edited items in the same order.
+///
+/// # Example
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+///     /* Opens the user's editor once per item */
+///     let items = vec![String::from("Hello"), String::from("Goodbye")];
+///     let translated = scrawl::edit_each(items)?;
+///     println!("{:?}", translated);
+/// #   Ok(())
+/// # }
+/// ```
+pub fn edit_each<I: IntoIterator<Item = String>>(items: I) -> Result<Vec<String>, ScrawlError> {
+    editor::new().edit_each(items, OnEmpty::Keep)
+}
+
+/// FromFile opens a text buffer with the content of the provided file in an editor. Returns a Readble struct on success, or `ScrawlError::FailedToCopyToTempFile` if the file doesn't exist or can't be read, before any editor is opened.
 ///
 /// # Example
 /// ```no_run
@@ -68,7 +229,9 @@ pub fn with<U: AsRef<[u8]>>(input: &U) -> Result<editor::Reader, Box<dyn Error>>
 /// #   Ok(())
 /// # }
 /// ```
-pub fn from_file<P: AsRef<Path>>(path: &P) -> Result<editor::Reader, Box<dyn Error>> {
+///
+/// Paths don't have to be UTF-8; they are used, and reported in errors, exactly as given.
+pub fn from_file<P: AsRef<Path>>(path: &P) -> Result<editor::Reader, ScrawlError> {
     editor::new().open(Contents::FromFile(path))
 }
 
@@ -85,6 +248,54 @@ pub fn from_file<P: AsRef<Path>>(path: &P) -> Result<editor::Reader, Box<dyn Err
 /// #   Ok(())
 /// # }
 /// ```
-pub fn edit_file<P: AsRef<Path>>(path: &P) -> Result<editor::Reader, Box<dyn Error>> {
+pub fn edit_file<P: AsRef<Path>>(path: &P) -> Result<editor::Reader, ScrawlError> {
     editor::new().edit(path)
 }
+
+/// EditConfig opens a configuration file in an editor and parses it once the user is done. If it doesn't parse, the error is written as a comment at the top of the file and the editor is opened again, until it does. Saving without making any changes gives up, returning `ScrawlError::InvalidConfig`. Formats are enabled with the `json`, `toml` and `yaml` features.
+///
+/// # Example
+/// ```no_run
+/// # use std::collections::HashMap;
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "toml")]
+/// # {
+///     use scrawl::ConfigFormat;
+///
+///     /* Keeps the user editing until config.toml is valid */
+///     let config: HashMap<String, String> = scrawl::edit_config(&"config.toml", ConfigFormat::Toml)?;
+///     println!("{config:?}");
+/// # }
+/// #   Ok(())
+/// # }
+/// ```
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+pub fn edit_config<T, P>(path: &P, format: ConfigFormat) -> Result<T, ScrawlError>
+where
+    T: serde::de::DeserializeOwned,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    loop {
+        /* The error comments from the last attempt aren't part of the config */
+        let before = fs::read_to_string(path)
+            .ok()
+            .map(|text| format.strip_annotations(&text));
+        let text = editor::new().edit(path)?.to_string()?;
+        let config = format.strip_annotations(&text);
+
+        match format.parse(&config) {
+            Err(e) if before.as_deref() != Some(config.as_str()) => {
+                fs::write(path, format.annotate(&config, &e))?
+            }
+            /* Parsed, or nothing was changed since the last error so the user has given up */
+            result => {
+                if config != text {
+                    fs::write(path, &config)?;
+                }
+                return result.map_err(ScrawlError::InvalidConfig);
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! # Logging
+//! The `log` macros Scrawl reports its choice of editor with, which compile to nothing without the `log` feature.
+#![deny(
+    missing_docs,
+    missing_debug_implementations,
+    missing_copy_implementations,
+    trivial_casts,
+    trivial_numeric_casts,
+    unstable_features,
+    unsafe_code,
+    unused_import_braces,
+    unused_qualifications
+)]
+
+#[cfg(feature = "log")]
+pub(crate) use log::{debug, error, info, warn};
+
+/* Type-checks the message, so it can't only break with the feature on, but never formats it */
+#[cfg(not(feature = "log"))]
+macro_rules! discard {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) use {discard as debug, discard as error, discard as info, discard as warn};
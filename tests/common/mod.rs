@@ -0,0 +1,31 @@
+//! Helpers shared by the integration tests
+use std::env;
+use std::ffi::OsString;
+use std::sync::{Mutex, MutexGuard};
+
+static ENV: Mutex<()> = Mutex::new(());
+
+/// Holds the other tests in the binary off the environment, putting it back as it was when dropped.
+pub struct EnvGuard {
+    vars: Vec<(OsString, OsString)>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for (name, _) in env::vars_os() {
+            env::remove_var(name);
+        }
+        for (name, value) in &self.vars {
+            env::set_var(name, value);
+        }
+    }
+}
+
+/// Serializes the tests in a binary, which share its environment variables, and starts each without a test replacement.
+pub fn env_lock() -> EnvGuard {
+    let lock = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let vars = env::vars_os().collect();
+    env::remove_var("SCRAWL_TEST_REPLACEMENT");
+    EnvGuard { vars, _lock: lock }
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use scrawl::{Contents, ScrawlError};
+
+#[test]
+fn extension_follows_the_seed_file() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "edited");
+    let dir = std::env::temp_dir().join(format!("scrawl-ext-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (notes, main, readme) = (
+        dir.join("notes.md"),
+        dir.join("main.rs"),
+        dir.join("README"),
+    );
+    for seed in [&notes, &main, &readme] {
+        fs::write(seed, "seed").unwrap();
+    }
+
+    let open = |path| {
+        scrawl::editor::new()
+            .open(Contents::FromFile(path))
+            .unwrap()
+    };
+    assert_eq!(open(&notes).extension(), ".md");
+    assert_eq!(open(&main).extension(), ".rs");
+    assert_eq!(open(&readme).extension(), ".txt");
+    let reader = scrawl::editor::new()
+        .ext("toml")
+        .open(Contents::FromFile(&main))
+        .unwrap();
+    assert_eq!(reader.extension(), ".toml");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn missing_file_fails_before_the_editor_opens() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "done");
+    match scrawl::from_file(&"does/not/exist.txt") {
+        Err(ScrawlError::FailedToCopyToTempFile(source)) => {
+            assert_eq!(source, Path::new("does/not/exist.txt"))
+        }
+        other => panic!("expected FailedToCopyToTempFile, got {other:?}"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_paths_are_used_as_given() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let _env = common::env_lock();
+    let dir = std::env::temp_dir().join(format!("scrawl_non_utf8_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let source = dir.join(OsStr::from_bytes(b"seed_\xff.txt"));
+    let dest = dir.join(OsStr::from_bytes(b"saved_\xfe.txt"));
+    fs::write(&source, "kept").unwrap();
+
+    let reader = scrawl::editor::new()
+        .editor("true")
+        .open(Contents::FromFile(&source))
+        .unwrap();
+    reader.to_file(&dest).unwrap();
+    assert_eq!(fs::read_to_string(&dest).unwrap(), "kept");
+    match reader.to_file_no_clobber(&dest) {
+        Err(ScrawlError::DestinationExists(path)) => assert_eq!(path, dest),
+        other => panic!("expected DestinationExists, got {other:?}"),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn first_available_skips_missing_files() {
+    let _env = common::env_lock();
+    let draft = std::env::temp_dir().join("scrawl_no_such_draft.txt");
+    let seed = Contents::FirstAvailable(vec![
+        Contents::FromOwnedPath(draft),
+        Contents::FromString(&"Dear ..."),
+    ]);
+    let reader = scrawl::editor::new().editor("true").open(seed).unwrap();
+    assert_eq!(reader.to_string().unwrap(), "Dear ...");
+}
+
+#[cfg(unix)]
+#[test]
+fn writer_fn_output_is_wrapped() {
+    use std::io::Write;
+
+    let _env = common::env_lock();
+    let seed = Contents::FromWriterFn(Box::new(|file| write!(file, "generated")));
+    let reader = scrawl::editor::new()
+        .editor("true")
+        .wrap("Write below", "Write above")
+        .open(seed)
+        .unwrap();
+    assert_eq!(reader.to_string().unwrap(), "generated\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn reference_comes_after_the_buffer() {
+    let _env = common::env_lock();
+    /* Quote the reference into the scratch buffer */
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"sed 's/^/> /' "$1" > "$0""#)
+        .arg("{}");
+    let reader = editor
+        .open_with_reference(Contents::FromString(&"fn main() {}\n"), Contents::Empty)
+        .unwrap();
+    assert_eq!(reader.to_string().unwrap(), "> fn main() {}\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_files_opens_every_path() {
+    let _env = common::env_lock();
+    let dir = std::env::temp_dir().join(format!("scrawl_edit_files_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let paths = vec![dir.join("a.txt"), dir.join("b.txt")];
+
+    /* The first path comes first and the others after the arguments */
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"for f in "$0" "$@"; do echo edited > "$f"; done"#)
+        .arg("{}");
+    let readers = editor.create(true).edit_files(paths.clone()).unwrap();
+    assert_eq!(readers.len(), 2);
+    assert_eq!(readers[1].to_string().unwrap(), "edited\n");
+
+    drop(readers);
+    assert!(paths.iter().all(|path| path.exists()));
+    fs::remove_dir_all(&dir).unwrap();
+}
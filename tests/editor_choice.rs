@@ -0,0 +1,133 @@
+mod common;
+
+use scrawl::{Contents, ScrawlError};
+
+#[test]
+fn empty_and_repeated_env_vars_are_skipped() {
+    let _env = common::env_lock();
+    std::env::set_var("EDITOR", "");
+    std::env::set_var("VISUAL", "vim");
+    std::env::set_var("PATH", "");
+    let result = scrawl::editor::new()
+        .env_vars(["VISUAL", "EDITOR"])
+        .open(Contents::Empty);
+    let Err(ScrawlError::EditorNotFound(tried)) = result else {
+        panic!("no editor should be found")
+    };
+    assert_eq!(tried[0], "vim");
+    assert!(tried.iter().all(|name| !name.is_empty()));
+    assert_eq!(tried.iter().filter(|name| *name == "vim").count(), 1);
+}
+
+#[test]
+fn ignore_env_skips_the_env_vars() {
+    let _env = common::env_lock();
+    std::env::set_var("EDITOR", "my-editor");
+    std::env::set_var("PATH", "");
+    let result = scrawl::editor::new().ignore_env(true).open(Contents::Empty);
+    let Err(ScrawlError::EditorNotFound(tried)) = result else {
+        panic!("no editor should be found")
+    };
+    assert!(!tried.iter().any(|name| name == "my-editor"));
+}
+
+#[test]
+fn edit_reports_the_editors_tried() {
+    let _env = common::env_lock();
+    std::env::set_var("EDITOR", "no-such-editor");
+    std::env::set_var("PATH", "");
+    match scrawl::editor::new().env_vars(["EDITOR"]).edit("notes.txt") {
+        Err(ScrawlError::EditorNotFound(tried)) => assert_eq!(tried[0], "no-such-editor"),
+        other => panic!("expected EditorNotFound, got {other:?}"),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn on_fallback_reports_the_editor_used_instead() {
+    use std::sync::{Arc, Mutex};
+
+    let _env = common::env_lock();
+    std::env::set_var("EDITOR", "my-alias");
+    let warning = Arc::new(Mutex::new(None));
+    let seen = Arc::clone(&warning);
+    scrawl::editor::new()
+        .env_vars(["EDITOR"])
+        .editor_for(scrawl::Os::Linux, "true")
+        .on_fallback(move |wanted, used| {
+            *seen.lock().unwrap() = Some(format!("{wanted:?} wasn't found, using {used:?}"));
+        })
+        .open(Contents::Empty)
+        .unwrap();
+    assert_eq!(
+        warning.lock().unwrap().as_deref(),
+        Some(r#""my-alias" wasn't found, using "true""#)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn unrunnable_editor_is_named_in_the_error() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let _env = common::env_lock();
+    /* An "editor" that is executable, but not a program */
+    let broken = std::env::temp_dir().join(format!("scrawl-broken-editor-{}", std::process::id()));
+    std::fs::write(&broken, b"\x00\x01 not a program").unwrap();
+    std::fs::set_permissions(&broken, std::fs::Permissions::from_mode(0o755)).unwrap();
+    match scrawl::editor::new().editor(&broken).open(Contents::Empty) {
+        Err(ScrawlError::FailedToOpenEditor(message)) => {
+            assert!(message.contains("scrawl-broken-editor"), "{message}")
+        }
+        other => panic!("expected FailedToOpenEditor, got {other:?}"),
+    }
+    std::fs::remove_file(&broken).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn non_executable_editor_is_not_replaced() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    let _env = common::env_lock();
+    let cwd = std::env::current_dir().unwrap();
+    let dir = std::env::temp_dir().join(format!("scrawl-not-executable-{}", std::process::id()));
+    fs::create_dir_all(dir.join("scripts")).unwrap();
+    fs::write(dir.join("vim"), "#!/bin/sh\n").unwrap();
+    fs::set_permissions(dir.join("vim"), fs::Permissions::from_mode(0o755)).unwrap();
+    fs::write(dir.join("scripts/edit.sh"), "#!/bin/sh\n").unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    std::env::set_var("PATH", &dir);
+    std::env::set_var("EDITOR", "./scripts/edit.sh");
+    std::env::remove_var("SCRAWL_EDITOR");
+    std::env::remove_var("VISUAL");
+
+    /* vim is installed, but isn't used instead */
+    let result = scrawl::editor::new().open(Contents::Empty);
+    std::env::set_current_dir(cwd).unwrap();
+    match result {
+        Err(ScrawlError::FailedToOpenEditor(message)) => {
+            assert!(
+                message.ends_with("edit.sh: the file is not executable"),
+                "{message}"
+            )
+        }
+        other => panic!("expected FailedToOpenEditor, got {other:?}"),
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn require_interactive_fails_without_a_terminal() {
+    use std::io::IsTerminal;
+
+    let _env = common::env_lock();
+    let result = scrawl::editor::new()
+        .editor("true")
+        .require_interactive(true)
+        .open(Contents::Empty);
+    if !std::io::stdout().is_terminal() {
+        assert!(matches!(result, Err(ScrawlError::NotInteractive)));
+    }
+}
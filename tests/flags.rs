@@ -0,0 +1,82 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use scrawl::{Contents, FlagInjection};
+
+/* A stand-in for vim that leaves the buffer alone */
+fn fake_vim(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("scrawl-flags-{name}-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let vim = dir.join("vim");
+    fs::write(&vim, "#!/bin/sh\n").unwrap();
+    fs::set_permissions(&vim, fs::Permissions::from_mode(0o755)).unwrap();
+    vim
+}
+
+#[test]
+fn requested_flags_survive_no_color() {
+    let _env = common::env_lock();
+    std::env::set_var("NO_COLOR", "1");
+    let vim = fake_vim("read-only");
+    let reader = scrawl::editor::new()
+        .editor(&vim)
+        .read_only(true)
+        .open(Contents::Empty)
+        .unwrap();
+    assert!(reader.args_used().iter().any(|arg| arg == "-R"));
+    fs::remove_dir_all(vim.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn own_flags_are_left_out_under_no_color() {
+    let _env = common::env_lock();
+    std::env::set_var("NO_COLOR", "1");
+    let vim = fake_vim("layout");
+    let open = |injection| {
+        scrawl::editor::new()
+            .editor(&vim)
+            .inject_flags(injection)
+            .open_with_reference(Contents::FromString(&"reference"), Contents::Empty)
+            .unwrap()
+    };
+    let split = |reader: &scrawl::editor::Reader| reader.args_used().iter().any(|arg| arg == "-o");
+    assert!(!split(&open(FlagInjection::Auto)));
+    assert!(split(&open(FlagInjection::Always)));
+    fs::remove_dir_all(vim.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn never_adds_no_flags() {
+    let _env = common::env_lock();
+    let vim = fake_vim("never");
+    let reader = scrawl::editor::new()
+        .editor(&vim)
+        .read_only(true)
+        .inject_flags(FlagInjection::Never)
+        .open(Contents::Empty)
+        .unwrap();
+    assert!(reader.args_used().is_empty());
+    fs::remove_dir_all(vim.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn cursor_at_end_moves_to_the_last_character() {
+    let _env = common::env_lock();
+    let vim = fake_vim("end-cursor");
+    let reader = scrawl::editor::new()
+        .editor(&vim)
+        .cursor_at_end(true)
+        .inject_flags(FlagInjection::Always)
+        .open(Contents::FromString(&"entry one\nentry two\n"))
+        .unwrap();
+    assert!(reader
+        .args_used()
+        .iter()
+        .any(|arg| arg == "+call setcursorcharpos(2, 10)"));
+    fs::remove_dir_all(vim.parent().unwrap()).unwrap();
+}
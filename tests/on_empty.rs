@@ -0,0 +1,67 @@
+mod common;
+
+use scrawl::{Contents, OnEmpty, ScrawlError};
+
+#[test]
+fn error_fails_on_an_empty_buffer() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "  \n");
+    let result = scrawl::editor::new()
+        .on_empty(OnEmpty::Error)
+        .open(Contents::FromString(&"Describe your change"));
+    assert!(matches!(result, Err(ScrawlError::EmptyBuffer)));
+}
+
+#[test]
+fn skip_fails_for_a_single_buffer() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "");
+    let result = scrawl::editor::new()
+        .on_empty(OnEmpty::Skip)
+        .open(Contents::Empty);
+    assert!(matches!(result, Err(ScrawlError::EmptyBuffer)));
+}
+
+#[test]
+fn reopen_fails_under_the_test_replacement() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "");
+    let result = scrawl::editor::new()
+        .on_empty(OnEmpty::Reopen {
+            message: "# Say something".into(),
+        })
+        .open(Contents::Empty);
+    assert!(matches!(result, Err(ScrawlError::EmptyBuffer)));
+}
+
+#[cfg(unix)]
+#[test]
+fn reopen_shows_the_message_until_something_is_saved() {
+    let _env = common::env_lock();
+    /* Saves nothing the first time, then answers the message */
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"if grep -q 'Say something' "$0"; then echo answer > "$0"; else : > "$0"; fi"#)
+        .arg("{}")
+        .on_empty(OnEmpty::Reopen {
+            message: "# Say something".into(),
+        });
+    let reader = editor.open(Contents::Empty).unwrap();
+    assert_eq!(reader.to_string().unwrap(), "answer\n");
+}
+
+#[test]
+fn edit_each_skips_and_stops_on_empty_items() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "");
+    let items = || vec![String::from("a"), String::from("b")];
+    let mut editor = scrawl::editor::new();
+    assert_eq!(editor.edit_each(items(), OnEmpty::Keep).unwrap(), ["", ""]);
+    assert!(editor.edit_each(items(), OnEmpty::Skip).unwrap().is_empty());
+    assert!(editor.edit_each(items(), OnEmpty::Stop).unwrap().is_empty());
+    assert!(matches!(
+        editor.edit_each(items(), OnEmpty::Error),
+        Err(ScrawlError::EmptyBuffer)
+    ));
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use scrawl::{Contents, ScrawlError};
+
+#[test]
+fn test_replacement_needs_no_editor() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "replaced");
+    std::env::set_var("PATH", "");
+    let reader = scrawl::editor::new()
+        .ignore_env(true)
+        .pipe_mode(true)
+        .open(Contents::Empty)
+        .unwrap();
+    assert_eq!(reader.to_string().unwrap(), "replaced");
+}
+
+#[test]
+fn captured_output_has_no_buffer_file() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "replaced");
+    let mut reader = scrawl::editor::new()
+        .pipe_mode(true)
+        .open(Contents::Empty)
+        .unwrap();
+    assert!(matches!(
+        reader.with_path(|_| ()),
+        Err(ScrawlError::BufferMissing(_))
+    ));
+    assert!(matches!(
+        reader.keep_alive(),
+        Err(ScrawlError::BufferMissing(_))
+    ));
+    assert!(matches!(
+        reader.to_bufreader(),
+        Err(ScrawlError::BufferMissing(_))
+    ));
+    assert!(matches!(
+        reader.reedit(),
+        Err(ScrawlError::BufferMissing(_))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn command_hook_cannot_undo_the_pipe() {
+    use std::process::Stdio;
+
+    let _env = common::env_lock();
+    let reader = scrawl::editor::new()
+        .editor("cat")
+        .pipe_mode(true)
+        .command_hook(|command| {
+            command.stdin(Stdio::null());
+        })
+        .open(Contents::FromString(&"still piped"))
+        .unwrap();
+    assert_eq!(reader.to_string().unwrap(), "still piped");
+}
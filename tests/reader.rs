@@ -0,0 +1,117 @@
+mod common;
+
+use std::io::Read;
+
+use scrawl::{Contents, ScrawlError};
+
+#[test]
+fn read_exact_bytes_keeps_the_wrapping() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "HEAD\n>8\nbody\n8<\nFOOT\n");
+    let reader = scrawl::editor::new()
+        .wrap("HEAD", "FOOT")
+        .sentinels(">8", "8<")
+        .open(Contents::Empty)
+        .unwrap();
+    assert_eq!(reader.read().unwrap(), b"body\n");
+    assert_eq!(
+        reader.read_exact_bytes().unwrap(),
+        b"HEAD\n>8\nbody\n8<\nFOOT\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn invalid_utf8_reports_its_offset() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor.arg("-c").arg(r#"printf 'ab\377' > "$0""#).arg("{}");
+    let reader = editor.open(Contents::Empty).unwrap();
+    assert!(matches!(
+        reader.to_string(),
+        Err(ScrawlError::FailedToCaptureInput(2))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn decoded_offset_counts_the_bom() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"printf '\357\273\277ab\377' > "$0""#)
+        .arg("{}");
+    let reader = editor.open(Contents::Empty).unwrap();
+    assert!(matches!(
+        reader.to_string_decoded(),
+        Err(ScrawlError::FailedToCaptureInput(5))
+    ));
+}
+
+#[test]
+fn entries_are_split_and_trimmed() {
+    let _env = common::env_lock();
+    std::env::set_var(
+        "SCRAWL_TEST_REPLACEMENT",
+        "---\nfirst\n---\n\n---\n  third\r\n---\n",
+    );
+    let reader = scrawl::editor::new().open(Contents::Empty).unwrap();
+    assert_eq!(
+        reader.split_into_entries("---").unwrap(),
+        ["first", "", "third"]
+    );
+}
+
+#[test]
+fn line_count_follows_on_read() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "a\nb\nc");
+    let reader = scrawl::editor::new()
+        .on_read(|text| text.lines().next().unwrap_or_default().into())
+        .open(Contents::Empty)
+        .unwrap();
+    assert_eq!(reader.to_string().unwrap(), "a");
+    assert_eq!(reader.line_count().unwrap(), 1);
+}
+
+#[test]
+fn tee_returns_what_it_writes() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "kept");
+    let copy = std::env::temp_dir().join(format!("scrawl_tee_{}.txt", std::process::id()));
+    let reader = scrawl::editor::new().open(Contents::Empty).unwrap();
+    assert_eq!(reader.tee(&copy).unwrap(), "kept");
+    assert_eq!(std::fs::read_to_string(&copy).unwrap(), "kept");
+    std::fs::remove_file(&copy).unwrap();
+}
+
+#[test]
+fn reader_streams_with_io_copy() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "streamed");
+    let mut reader = scrawl::editor::new().open(Contents::Empty).unwrap();
+    let mut sink = Vec::new();
+    std::io::copy(&mut reader, &mut sink).unwrap();
+    assert_eq!(sink, b"streamed");
+    assert_eq!(Read::read(&mut reader, &mut [0; 8]).unwrap(), 0);
+}
+
+#[test]
+fn abort_on_cancels_the_edit() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "half a thought\nABORT\n");
+    let result = scrawl::editor::new()
+        .abort_on("ABORT")
+        .open(Contents::FromString(&"# Write ABORT to cancel"));
+    assert!(matches!(result, Err(ScrawlError::Aborted)));
+}
+
+#[test]
+fn confirm_needs_yes() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "YES\n");
+    assert!(scrawl::confirm("Delete every branch except main?").unwrap());
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "yes\n");
+    assert!(!scrawl::confirm("Delete every branch except main?").unwrap());
+}
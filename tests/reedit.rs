@@ -0,0 +1,61 @@
+#![cfg(unix)]
+
+mod common;
+
+use scrawl::{Contents, ScrawlError};
+
+#[test]
+fn reedit_uses_the_editor_from_env_vars() {
+    let _env = common::env_lock();
+    std::env::set_var("MYAPP_EDITOR", r#"sh -c 'echo run >> "$0"'"#);
+    let reader = scrawl::editor::new()
+        .env_vars(["MYAPP_EDITOR"])
+        .open(Contents::Empty)
+        .unwrap();
+    std::env::remove_var("MYAPP_EDITOR");
+    let reader = reader.reedit().unwrap();
+    assert_eq!(reader.to_string().unwrap(), "run\nrun\n");
+}
+
+#[test]
+fn reedit_applies_the_command_hook_again() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"echo "$SCRAWL_GREETING" >> "$0""#)
+        .arg("{}")
+        .command_hook(|command| {
+            command.env("SCRAWL_GREETING", "hi");
+        });
+    let reader = editor.open(Contents::Empty).unwrap();
+    let reader = reader.reedit().unwrap();
+    assert_eq!(reader.to_string().unwrap(), "hi\nhi\n");
+}
+
+#[test]
+fn reedit_reports_a_failed_editor() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"if [ -s "$0" ]; then echo 'boom on reedit' >&2; exit 2; fi; echo first > "$0""#)
+        .arg("{}");
+    let reader = editor.open(Contents::Empty).unwrap();
+    match reader.reedit() {
+        Err(ScrawlError::FailedToOpenEditor(message)) => {
+            assert!(message.contains("boom on reedit"), "{message}")
+        }
+        other => panic!("expected FailedToOpenEditor, got {other:?}"),
+    }
+}
+
+#[test]
+fn reedit_runs_the_editor_on_the_same_buffer() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor.arg("-c").arg("echo again >> {}");
+    let reader = editor.open(Contents::FromString(&"first\n")).unwrap();
+    let reader = reader.reedit().unwrap();
+    assert_eq!(reader.to_string().unwrap(), "first\nagain\nagain\n");
+}
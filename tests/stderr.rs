@@ -0,0 +1,53 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use scrawl::{Contents, ScrawlError};
+
+#[test]
+fn failed_editor_reports_its_stderr() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg("echo 'unknown option --wait' >&2; exit 2")
+        .arg("{}");
+    match editor.open(Contents::Empty) {
+        Err(ScrawlError::FailedToOpenEditor(message)) => {
+            assert!(message.contains("unknown option --wait"))
+        }
+        other => panic!("expected FailedToOpenEditor, got {other:?}"),
+    }
+}
+
+#[test]
+fn caller_stderr_is_not_captured() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg("echo 'boom on stderr' >&2; exit 2")
+        .arg("{}")
+        .stderr(Stdio::null());
+    match editor.open(Contents::Empty) {
+        Ok(reader) => assert!(!reader.exit_status().unwrap().success()),
+        Err(e) => assert!(!e.to_string().contains("boom on stderr"), "{e}"),
+    }
+}
+
+#[test]
+fn forking_editor_returns_once_it_exits() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"echo hi > "$0"; sleep 5 & exit 0"#)
+        .arg("{}");
+    let started = Instant::now();
+    let reader = editor.open(Contents::Empty).unwrap();
+    assert_eq!(reader.to_string().unwrap(), "hi\n");
+    assert!(started.elapsed() < Duration::from_secs(4));
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+
+use scrawl::{Contents, ScrawlError};
+
+/* Points $TMPDIR at a file, which nothing can be created inside, and $XDG_RUNTIME_DIR at a directory */
+#[cfg(unix)]
+fn unwritable_temp_dir(name: &str) -> (PathBuf, PathBuf) {
+    let id = std::process::id();
+    let not_a_dir = std::env::temp_dir().join(format!("scrawl_{name}_not_a_dir_{id}"));
+    let fallback = std::env::temp_dir().join(format!("scrawl_{name}_fallback_{id}"));
+    fs::write(&not_a_dir, "").unwrap();
+    fs::create_dir_all(&fallback).unwrap();
+    std::env::set_var("TMPDIR", &not_a_dir);
+    std::env::set_var("XDG_RUNTIME_DIR", &fallback);
+    (not_a_dir, fallback)
+}
+
+#[cfg(unix)]
+#[test]
+fn buffer_falls_back_to_the_runtime_dir() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "done");
+    let (not_a_dir, fallback) = unwritable_temp_dir("runtime");
+    let session = scrawl::editor::new().open_session(Contents::Empty).unwrap();
+    assert!(session.path.starts_with(&fallback));
+    drop(session);
+    fs::remove_file(&not_a_dir).unwrap();
+    fs::remove_dir_all(&fallback).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn fallback_buffers_are_listed() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "done");
+    let (not_a_dir, fallback) = unwritable_temp_dir("sweep");
+    let session = scrawl::editor::new().open_session(Contents::Empty).unwrap();
+    assert!(scrawl::editor::list_temp_files()
+        .unwrap()
+        .contains(&session.path));
+    drop(session);
+    fs::remove_file(&not_a_dir).unwrap();
+    fs::remove_dir_all(&fallback).unwrap();
+}
+
+#[test]
+fn concurrent_opens_share_a_new_subdir() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "done");
+    let subdir = format!("scrawl_race_{}", std::process::id());
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let subdir = subdir.clone();
+            std::thread::spawn(move || {
+                scrawl::editor::new()
+                    .temp_subdir(subdir)
+                    .open(Contents::Empty)
+                    .map(|reader| reader.to_string().ok())
+            })
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap().unwrap().as_deref(), Some("done"));
+    }
+    fs::remove_dir_all(std::env::temp_dir().join(subdir)).ok();
+}
+
+#[test]
+fn subdir_buffer_is_removed_on_drop() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "done");
+    let subdir = format!("scrawl_cleanup_{}", std::process::id());
+    let session = scrawl::editor::new()
+        .temp_subdir(&subdir)
+        .open_session(Contents::Empty)
+        .unwrap();
+    let buffer = session.path.clone();
+    assert!(buffer.starts_with(std::env::temp_dir().join(&subdir)));
+    assert!(buffer.exists());
+    drop(session);
+    assert!(!buffer.exists());
+    fs::remove_dir_all(std::env::temp_dir().join(subdir)).ok();
+}
+
+#[test]
+fn missing_parents_are_created_unless_disabled() {
+    let _env = common::env_lock();
+    std::env::set_var("SCRAWL_TEST_REPLACEMENT", "done");
+    let base = format!("scrawl_nested_{}", std::process::id());
+    let session = scrawl::editor::new()
+        .temp_subdir(format!("{base}/created/buffers"))
+        .open_session(Contents::Empty)
+        .unwrap();
+    assert!(session
+        .path
+        .parent()
+        .unwrap()
+        .ends_with(format!("{base}/created/buffers")));
+    drop(session);
+
+    let result = scrawl::editor::new()
+        .temp_subdir(format!("{base}/missing/buffers"))
+        .create_temp_parents(false)
+        .open(Contents::Empty);
+    assert!(matches!(
+        result,
+        Err(ScrawlError::FailedToCreateTempfile(_))
+    ));
+    fs::remove_dir_all(std::env::temp_dir().join(base)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn atomic_save_backups_are_removed_on_drop() {
+    let _env = common::env_lock();
+    /* Saves like an atomic-save editor: write a new file, keep a backup, rename over the buffer */
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor
+        .arg("-c")
+        .arg(r#"cp "$0" "$0~" && echo saved > "$0.new" && mv "$0.new" "$0""#)
+        .arg("{}");
+    let session = editor.open_session(Contents::FromString(&"draft")).unwrap();
+    assert_eq!(session.reader.to_string().unwrap(), "saved\n");
+
+    let mut backup = session.path.clone().into_os_string();
+    backup.push("~");
+    drop(session);
+    assert!(!std::path::Path::new(&backup).exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn kept_alive_buffer_outlives_its_reader() {
+    let _env = common::env_lock();
+    let mut editor = scrawl::editor::new().editor("sh");
+    editor.arg("-c").arg("echo uploaded > {}");
+    let mut reader = editor.open(Contents::Empty).unwrap();
+    let guard = reader.keep_alive().unwrap();
+    let path = guard.path().to_path_buf();
+    drop(reader);
+
+    let task = std::thread::spawn(move || fs::read_to_string(guard.path()).unwrap());
+    assert_eq!(task.join().unwrap(), "uploaded\n");
+    assert!(!path.exists());
+}
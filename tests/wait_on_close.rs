@@ -0,0 +1,22 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::time::{Duration, Instant};
+
+use scrawl::Contents;
+
+#[test]
+fn limit_stops_waiting_for_a_save() {
+    let _env = common::env_lock();
+    /* An "editor" that returns at once, without the user ever saving */
+    let started = Instant::now();
+    let reader = scrawl::editor::new()
+        .editor("true")
+        .wait_on_close(Duration::from_millis(100))
+        .wait_on_close_limit(Duration::from_millis(500))
+        .open(Contents::FromString(&"draft"))
+        .unwrap();
+    assert_eq!(reader.to_string().unwrap(), "draft");
+    assert!(started.elapsed() < Duration::from_secs(5));
+}